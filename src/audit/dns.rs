@@ -0,0 +1,375 @@
+use std::net::IpAddr;
+
+use super::finding::{AuditReport, Finding, Rule, Severity};
+use crate::context::ResolveContext;
+use crate::nss;
+
+pub const RULES: [Rule; 8] = [
+    Rule { id: "hosts-overrides-public-domain", description: "/etc/hosts does not redirect or block well-known public domains" },
+    Rule { id: "public-nameserver", description: "Hosts on internal DNS do not also query public nameservers" },
+    Rule { id: "single-label-search-domain", description: "Search domains are fully qualified" },
+    Rule { id: "search-domain-leak", description: "Private search domains are not sent to public nameservers" },
+    Rule { id: "world-writable", description: "Resolver configuration is not world-writable" },
+    Rule { id: "not-root-owned", description: "Resolver configuration is owned by root" },
+    Rule { id: "missing-nss-module", description: "Every nsswitch.conf source has its libnss module installed" },
+    Rule { id: "dnssec-disabled", description: "systemd-resolved does not disable DNSSEC" },
+];
+
+/// Domains whose hijacking in /etc/hosts is a known persistence or
+/// update-blocking trick: search, OS and package updates, code hosting,
+/// banking and security vendors.
+const WELL_KNOWN_DOMAINS: [&str; 24] = [
+    "google.com",
+    "googleapis.com",
+    "gstatic.com",
+    "microsoft.com",
+    "windowsupdate.com",
+    "live.com",
+    "office.com",
+    "apple.com",
+    "icloud.com",
+    "amazon.com",
+    "amazonaws.com",
+    "facebook.com",
+    "github.com",
+    "githubusercontent.com",
+    "gitlab.com",
+    "cloudflare.com",
+    "paypal.com",
+    "debian.org",
+    "ubuntu.com",
+    "fedoraproject.org",
+    "pypi.org",
+    "npmjs.org",
+    "crates.io",
+    "virustotal.com",
+];
+
+/// Top-level labels that are not delegated in the public root zone; queries
+/// for them that reach public DNS disclose internal names.
+const PRIVATE_TLDS: [&str; 9] = ["corp", "home", "internal", "intranet", "lan", "local", "localdomain", "private", "home.arpa"];
+
+/// Sources glibc carries itself (libnss_files and libnss_dns are part of
+/// libc since 2.34).
+const BUILTIN_NSS_SOURCES: [&str; 2] = ["files", "dns"];
+
+const RESOLV_CONF_PATHS: [&str; 2] = ["/etc/resolv.conf", "/run/systemd/resolve/resolv.conf"];
+const PROTECTED_FILES: [&str; 3] = ["/etc/nsswitch.conf", "/etc/resolv.conf", "/etc/hosts"];
+
+const RESOLVED_CONF: &str = "/etc/systemd/resolved.conf";
+const RESOLVED_DROPIN_DIRS: [&str; 3] = [
+    "/usr/lib/systemd/resolved.conf.d",
+    "/run/systemd/resolved.conf.d",
+    "/etc/systemd/resolved.conf.d",
+];
+
+fn finding(rule: &'static str, severity: Severity, file: &str, line: usize, message: String, remediation: String) -> Finding {
+    Finding {
+        rule,
+        severity,
+        file: file.to_string(),
+        line,
+        message,
+        remediation: Some(remediation),
+    }
+}
+
+fn under_domain(name: &str, domain: &str) -> bool {
+    name == domain || name.ends_with(&format!(".{}", domain))
+}
+
+/// Loopback, RFC1918, shared (RFC 6598), link-local and unique local addresses.
+fn is_internal_address(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback() || v4.is_private() || v4.is_link_local() || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn is_sinkhole(addr: &IpAddr) -> bool {
+    addr.is_unspecified() || addr.is_loopback()
+}
+
+fn is_private_domain(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    PRIVATE_TLDS.iter().any(|tld| under_domain(&domain, tld))
+}
+
+fn audit_hosts(ctx: &ResolveContext, report: &mut AuditReport) {
+    let Ok(entries) = ctx.open("/etc/hosts").and_then(nss::hosts::parse_hosts) else {
+        return;
+    };
+    for entry in entries {
+        let Ok(addr) = entry.ip.split('%').next().unwrap_or_default().parse::<IpAddr>() else {
+            continue;
+        };
+        for name in &entry.names {
+            let lower = name.trim_end_matches('.').to_ascii_lowercase();
+            let Some(domain) = WELL_KNOWN_DOMAINS.iter().find(|domain| under_domain(&lower, domain)) else {
+                continue;
+            };
+            let (severity, what) = if is_sinkhole(&addr) {
+                (Severity::Medium, "blocks")
+            } else {
+                (Severity::High, "redirects")
+            };
+            report.findings.push(finding(
+                "hosts-overrides-public-domain",
+                severity,
+                &entry.source,
+                entry.line,
+                format!("{} {} {} (under {}) by pointing it at {}", entry.source, what, name, domain, entry.ip),
+                "Remove the line unless the override is deliberate; unexpected entries for update, search or \
+                 security domains are a common malware persistence trick"
+                    .to_string(),
+            ));
+        }
+    }
+}
+
+struct ResolvLine {
+    line: usize,
+    keyword: String,
+    values: Vec<String>,
+}
+
+fn resolv_lines(content: &str) -> Vec<ResolvLine> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.trim();
+            // Skip empty lines and comments
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                return None;
+            }
+            let mut parts = line.split_whitespace().map(String::from);
+            Some(ResolvLine {
+                line: index + 1,
+                keyword: parts.next()?,
+                values: parts.collect(),
+            })
+        })
+        .collect()
+}
+
+fn audit_resolv_conf(ctx: &ResolveContext, path: &str, report: &mut AuditReport) {
+    let Ok(content) = ctx.read_to_string(path) else {
+        return;
+    };
+    let lines = resolv_lines(&content);
+
+    let nameservers: Vec<(&ResolvLine, IpAddr)> = lines
+        .iter()
+        .filter(|l| l.keyword == "nameserver")
+        .filter_map(|l| Some((l, l.values.first()?.split('%').next()?.parse().ok()?)))
+        .collect();
+    let search: Vec<(&ResolvLine, &String)> = lines
+        .iter()
+        .filter(|l| l.keyword == "search" || l.keyword == "domain")
+        .flat_map(|l| l.values.iter().map(move |v| (l, v)))
+        .collect();
+
+    let public: Vec<&(&ResolvLine, IpAddr)> = nameservers.iter().filter(|(_, addr)| !is_internal_address(addr)).collect();
+    let internal_host = nameservers.iter().any(|(_, addr)| is_internal_address(addr) && !addr.is_loopback())
+        || search.iter().any(|(_, domain)| is_private_domain(domain));
+
+    if internal_host {
+        for (line, addr) in &public {
+            report.findings.push(finding(
+                "public-nameserver",
+                Severity::Medium,
+                path,
+                line.line,
+                format!(
+                    "nameserver {} is a public address on a host that also uses internal DNS; internal names are sent to it whenever it is queried",
+                    addr
+                ),
+                "Point resolv.conf only at internal resolvers and let them forward to public DNS".to_string(),
+            ));
+        }
+    }
+
+    for (line, domain) in &search {
+        let domain = domain.trim_end_matches('.');
+        if !domain.contains('.') {
+            report.findings.push(finding(
+                "single-label-search-domain",
+                Severity::Medium,
+                path,
+                line.line,
+                format!(
+                    "search domain {} is a single label, so a lookup of printer becomes printer.{}, a query for a top-level domain that public DNS sees",
+                    domain, domain
+                ),
+                "Use a fully qualified search domain you control (e.g. corp.example.com)".to_string(),
+            ));
+        } else if is_private_domain(domain) && !public.is_empty() {
+            report.findings.push(finding(
+                "search-domain-leak",
+                Severity::High,
+                path,
+                line.line,
+                format!(
+                    "search domain {} is not delegated in public DNS but public nameservers are configured; single-label lookups leak as <name>.{}",
+                    domain, domain
+                ),
+                "Remove public nameservers from this resolv.conf or move internal names under a registered domain".to_string(),
+            ));
+        }
+    }
+}
+
+fn audit_permissions(ctx: &ResolveContext, report: &mut AuditReport) {
+    for path in PROTECTED_FILES {
+        let Ok(meta) = ctx.metadata(path) else {
+            continue;
+        };
+        if meta.mode & 0o002 != 0 {
+            report.findings.push(finding(
+                "world-writable",
+                Severity::High,
+                path,
+                0,
+                format!("{} is world-writable (mode {:o}); any local user can redirect name resolution", path, meta.mode),
+                format!("chmod o-w {}", path),
+            ));
+        } else if meta.uid != 0 {
+            report.findings.push(finding(
+                "not-root-owned",
+                Severity::Medium,
+                path,
+                0,
+                format!("{} is owned by uid {}, who can redirect name resolution", path, meta.uid),
+                format!("chown root: {}", path),
+            ));
+        }
+    }
+}
+
+/// Directories glibc's dynamic loader searches for `libnss_<source>.so.2`.
+fn library_dirs() -> Vec<String> {
+    let triplet = format!("{}-linux-gnu", std::env::consts::ARCH);
+    let mut dirs: Vec<String> = ["/lib", "/lib64", "/usr/lib", "/usr/lib64"].iter().map(|d| d.to_string()).collect();
+    dirs.push(format!("/lib/{}", triplet));
+    dirs.push(format!("/usr/lib/{}", triplet));
+    dirs
+}
+
+fn audit_nss_modules(ctx: &ResolveContext, report: &mut AuditReport) {
+    let Ok(content) = ctx.read_to_string("/etc/nsswitch.conf") else {
+        return;
+    };
+    let dirs = library_dirs();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((database, value)) = line.split_once(':') else {
+            continue;
+        };
+        for source in nss::nsswitch::parse_sources(value) {
+            if BUILTIN_NSS_SOURCES.contains(&source.name.as_str()) {
+                continue;
+            }
+            let library = format!("libnss_{}.so.2", source.name);
+            if dirs.iter().any(|dir| ctx.metadata(&format!("{}/{}", dir, library)).is_ok()) {
+                continue;
+            }
+            report.findings.push(finding(
+                "missing-nss-module",
+                Severity::Medium,
+                "/etc/nsswitch.conf",
+                index + 1,
+                format!(
+                    "{} lists {} but {} is not installed; glibc treats the source as UNAVAIL on every lookup",
+                    database.trim(),
+                    source.name,
+                    library
+                ),
+                format!("Install the package providing {} or remove {} from the {} line", library, source.name, database.trim()),
+            ));
+        }
+    }
+}
+
+/// The effective `DNSSEC=` setting: resolved.conf, then drop-ins ordered by
+/// file name, where /etc overrides /run and /usr/lib for the same name.
+fn resolved_dnssec(ctx: &ResolveContext) -> Option<(String, usize, String)> {
+    let mut files = vec![RESOLVED_CONF.to_string()];
+    let mut dropins: Vec<(String, String)> = Vec::new();
+    for dir in RESOLVED_DROPIN_DIRS {
+        for name in ctx.read_dir(dir).unwrap_or_default().into_iter().filter(|n| n.ends_with(".conf")) {
+            dropins.retain(|(existing, _)| existing != &name);
+            dropins.push((name.clone(), format!("{}/{}", dir, name)));
+        }
+    }
+    dropins.sort();
+    files.extend(dropins.into_iter().map(|(_, path)| path));
+
+    let mut setting = None;
+    for file in files {
+        let Ok(content) = ctx.read_to_string(&file) else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            if let Some(value) = line.trim().strip_prefix("DNSSEC=") {
+                setting = Some((file.clone(), index + 1, value.trim().to_string()));
+            }
+        }
+    }
+    setting
+}
+
+fn audit_resolved(ctx: &ResolveContext, report: &mut AuditReport) {
+    match resolved_dnssec(ctx) {
+        Some((file, line, value)) if matches!(value.to_ascii_lowercase().as_str(), "no" | "false" | "0" | "off") => {
+            report.findings.push(finding(
+                "dnssec-disabled",
+                Severity::Low,
+                &file,
+                line,
+                format!("systemd-resolved has DNSSEC={}, so forged answers are accepted", value),
+                "Set DNSSEC=allow-downgrade (or yes where every upstream validates) and restart systemd-resolved".to_string(),
+            ));
+        }
+        Some(_) => {}
+        None if ctx.metadata(RESOLVED_CONF).is_ok() => {
+            report.notes.push("DNSSEC= is not set for systemd-resolved; its build-time default applies".to_string());
+        }
+        None => {}
+    }
+}
+
+/// Audits how the target resolves host names: /etc/hosts, resolv.conf,
+/// nsswitch.conf and systemd-resolved configuration.
+pub fn audit_dns(ctx: &ResolveContext) -> AuditReport {
+    let mut report = AuditReport {
+        rules: &RULES,
+        ..Default::default()
+    };
+    let replay = ctx.sysroot.as_ref().is_some_and(|sysroot| sysroot.replay.is_some());
+
+    audit_hosts(ctx, &mut report);
+    for path in RESOLV_CONF_PATHS {
+        audit_resolv_conf(ctx, path, &mut report);
+    }
+    if replay {
+        // Snapshot bundles record contents, not owners, modes or libraries
+        report.notes.push("file permissions and NSS modules are not part of the snapshot and were not checked".to_string());
+    } else {
+        audit_permissions(ctx, &mut report);
+        audit_nss_modules(ctx, &mut report);
+    }
+    audit_resolved(ctx, &mut report);
+    report.sort();
+    report
+}
@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
+
+use super::finding::{AuditReport, Finding, Rule, Severity};
+use crate::context::ResolveContext;
+use crate::nss::group::{self, GroupEntry};
+use crate::nss::passwd::{self, UserEntry};
+
+/// What glibc's getusershell() returns when /etc/shells is missing.
+const DEFAULT_SHELLS: [&str; 2] = ["/bin/sh", "/bin/csh"];
+
+/// Special-purpose account shells (Debian's sync, halt and shutdown users).
+const SPECIAL_SHELLS: [&str; 3] = ["/bin/sync", "/sbin/halt", "/sbin/shutdown"];
+
+pub const RULES: [Rule; 12] = [
+    Rule { id: "invalid-id", description: "Uid and gid fields are numbers" },
+    Rule { id: "extra-uid0", description: "Only root may have uid 0" },
+    Rule { id: "duplicate-user-name", description: "User names are unique in /etc/passwd" },
+    Rule { id: "duplicate-uid", description: "Each uid belongs to one user" },
+    Rule { id: "missing-primary-group", description: "Every primary gid is defined in /etc/group" },
+    Rule { id: "duplicate-group-name", description: "Group names are unique in /etc/group" },
+    Rule { id: "duplicate-gid", description: "Each gid belongs to one group" },
+    Rule { id: "unknown-group-member", description: "Group members are users in /etc/passwd" },
+    Rule { id: "unlisted-shell", description: "Login shells are listed in /etc/shells" },
+    Rule { id: "system-user-login-shell", description: "System users do not have interactive shells" },
+    Rule { id: "missing-home", description: "Home directories of login users exist" },
+    Rule { id: "home-owner", description: "Home directories are owned by their user" },
+];
+
+/// The uid ranges from /etc/login.defs that separate system from regular users.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginDefs {
+    pub uid_min: u32,
+    pub sys_uid_min: u32,
+    pub sys_uid_max: u32,
+}
+
+impl Default for LoginDefs {
+    // shadow-utils defaults when the keys are absent
+    fn default() -> Self {
+        LoginDefs {
+            uid_min: 1000,
+            sys_uid_min: 101,
+            sys_uid_max: 999,
+        }
+    }
+}
+
+pub fn parse_login_defs<R: BufRead>(reader: R) -> io::Result<LoginDefs> {
+    let mut defs = LoginDefs::default();
+    let mut sys_uid_max = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let Ok(value) = value.parse::<u32>() else {
+            continue;
+        };
+        match key {
+            "UID_MIN" => defs.uid_min = value,
+            "SYS_UID_MIN" => defs.sys_uid_min = value,
+            "SYS_UID_MAX" => sys_uid_max = Some(value),
+            _ => {}
+        }
+    }
+
+    defs.sys_uid_max = sys_uid_max.unwrap_or(defs.uid_min.saturating_sub(1));
+    Ok(defs)
+}
+
+pub fn parse_shells<R: BufRead>(reader: R) -> io::Result<Vec<String>> {
+    let mut shells = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        shells.push(line.to_string());
+    }
+
+    Ok(shells)
+}
+
+/// An empty shell field means /bin/sh to login(1).
+fn effective_shell(user: &UserEntry) -> &str {
+    if user.shell.is_empty() {
+        "/bin/sh"
+    } else {
+        &user.shell
+    }
+}
+
+fn refuses_login(shell: &str) -> bool {
+    shell.ends_with("/nologin") || shell.ends_with("/false") || SPECIAL_SHELLS.contains(&shell)
+}
+
+fn finding(rule: &'static str, severity: Severity, source: &str, line: usize, message: String) -> Finding {
+    Finding {
+        rule,
+        severity,
+        file: source.to_string(),
+        line,
+        message,
+        remediation: None,
+    }
+}
+
+/// Pairs each repeated entry with the first entry that has the same key.
+fn duplicates<T, K: std::hash::Hash + Eq>(entries: &[T], key: impl Fn(&T) -> K) -> Vec<(&T, &T)> {
+    let mut first: HashMap<K, &T> = HashMap::new();
+    let mut found = Vec::new();
+    for entry in entries {
+        match first.get(&key(entry)) {
+            Some(original) => found.push((entry, *original)),
+            None => {
+                first.insert(key(entry), entry);
+            }
+        }
+    }
+    found
+}
+
+/// Reports lines whose id fields (by index, with a name for each) are not
+/// numbers. glibc ignores such lines, and so do the parsers, so they never
+/// reach the other checks.
+fn audit_ids<R: BufRead>(reader: R, source: &str, min_fields: usize, id_fields: &[(usize, &str)], findings: &mut Vec<Finding>) -> io::Result<()> {
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split(':').collect();
+        if parts.len() < min_fields {
+            continue;
+        }
+        for &(field, what) in id_fields {
+            if passwd::parse_id(parts[field]).is_none() {
+                findings.push(finding(
+                    "invalid-id",
+                    Severity::Medium,
+                    source,
+                    index + 1,
+                    format!("{} has {} {:?}, which is not a number; lookups ignore the line", parts[0], what, parts[field]),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn audit_users(users: &[UserEntry], groups: &[GroupEntry], findings: &mut Vec<Finding>) {
+    for user in users.iter().filter(|u| u.uid == 0 && u.name != "root") {
+        findings.push(finding(
+            "extra-uid0",
+            Severity::High,
+            &user.source,
+            user.line,
+            format!("{} has uid 0 and is equivalent to root", user.name),
+        ));
+    }
+    for (user, original) in duplicates(users, |u| u.name.clone()) {
+        findings.push(finding(
+            "duplicate-user-name",
+            Severity::High,
+            &user.source,
+            user.line,
+            format!("user {} is already defined on line {}; lookups only ever return that entry", user.name, original.line),
+        ));
+    }
+    for (user, original) in duplicates(users, |u| u.uid) {
+        // Same-name repeats and extra uid 0 accounts are reported above
+        if user.name == original.name || user.uid == 0 {
+            continue;
+        }
+        findings.push(finding(
+            "duplicate-uid",
+            Severity::Medium,
+            &user.source,
+            user.line,
+            format!("{} shares uid {} with {} (line {}); files owned by one belong to both", user.name, user.uid, original.name, original.line),
+        ));
+    }
+
+    let gids: HashSet<u32> = groups.iter().map(|g| g.gid).collect();
+    for user in users.iter().filter(|u| !gids.contains(&u.gid)) {
+        findings.push(finding(
+            "missing-primary-group",
+            Severity::Medium,
+            &user.source,
+            user.line,
+            format!("primary gid {} of {} is not defined in /etc/group", user.gid, user.name),
+        ));
+    }
+}
+
+fn audit_groups(users: &[UserEntry], groups: &[GroupEntry], findings: &mut Vec<Finding>) {
+    for (group, original) in duplicates(groups, |g| g.name.clone()) {
+        findings.push(finding(
+            "duplicate-group-name",
+            Severity::High,
+            &group.source,
+            group.line,
+            format!("group {} is already defined on line {}; lookups only ever return that entry", group.name, original.line),
+        ));
+    }
+    for (group, original) in duplicates(groups, |g| g.gid) {
+        if group.name == original.name {
+            continue;
+        }
+        findings.push(finding(
+            "duplicate-gid",
+            Severity::Medium,
+            &group.source,
+            group.line,
+            format!("{} shares gid {} with {} (line {})", group.name, group.gid, original.name, original.line),
+        ));
+    }
+
+    let names: HashSet<&str> = users.iter().map(|u| u.name.as_str()).collect();
+    for group in groups {
+        for member in group.members.iter().filter(|m| !names.contains(m.as_str())) {
+            findings.push(finding(
+                "unknown-group-member",
+                Severity::Low,
+                &group.source,
+                group.line,
+                format!("group {} lists {}, which is not a user in /etc/passwd", group.name, member),
+            ));
+        }
+    }
+}
+
+fn audit_logins(
+    ctx: &ResolveContext,
+    users: &[UserEntry],
+    shells: &[String],
+    defs: &LoginDefs,
+    check_homes: bool,
+    findings: &mut Vec<Finding>,
+) {
+    for user in users {
+        let shell = effective_shell(user);
+        if refuses_login(shell) {
+            continue;
+        }
+        if !shells.iter().any(|s| s == shell) {
+            findings.push(finding(
+                "unlisted-shell",
+                Severity::Low,
+                &user.source,
+                user.line,
+                format!("login shell {} of {} is not listed in /etc/shells", shell, user.name),
+            ));
+        }
+        if user.uid != 0 && user.uid >= defs.sys_uid_min && user.uid <= defs.sys_uid_max {
+            findings.push(finding(
+                "system-user-login-shell",
+                Severity::Medium,
+                &user.source,
+                user.line,
+                format!(
+                    "{} (uid {}) is in the system range {}-{} from /etc/login.defs but has the interactive shell {}",
+                    user.name, user.uid, defs.sys_uid_min, defs.sys_uid_max, shell
+                ),
+            ));
+        }
+
+        if !check_homes || user.home.is_empty() || user.home == "/" {
+            continue;
+        }
+        match ctx.metadata(&user.home) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => findings.push(finding(
+                "missing-home",
+                Severity::Low,
+                &user.source,
+                user.line,
+                format!("home directory {} of {} does not exist", user.home, user.name),
+            )),
+            Err(_) => {}
+            Ok(meta) if !meta.is_dir => findings.push(finding(
+                "missing-home",
+                Severity::Low,
+                &user.source,
+                user.line,
+                format!("home {} of {} is not a directory", user.home, user.name),
+            )),
+            Ok(meta) if meta.uid != user.uid => findings.push(finding(
+                "home-owner",
+                Severity::Medium,
+                &user.source,
+                user.line,
+                format!("home directory {} of {} (uid {}) is owned by uid {}", user.home, user.name, user.uid, meta.uid),
+            )),
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Audits /etc/passwd and /etc/group as the target sees them. Shell and home
+/// checks only apply to accounts that accept logins.
+pub fn audit_identities(ctx: &ResolveContext) -> AuditReport {
+    let mut report = AuditReport {
+        rules: &RULES,
+        ..Default::default()
+    };
+
+    let users = match ctx.open("/etc/passwd").and_then(passwd::parse_passwd) {
+        Ok(users) => users,
+        Err(e) => {
+            report.notes.push(format!("{} could not be read: {}", ctx.describe("/etc/passwd"), e));
+            Vec::new()
+        }
+    };
+    let groups = match ctx.open("/etc/group").and_then(group::parse_group) {
+        Ok(groups) => groups,
+        Err(e) => {
+            report.notes.push(format!("{} could not be read: {}", ctx.describe("/etc/group"), e));
+            Vec::new()
+        }
+    };
+    let shells = ctx.open("/etc/shells").and_then(parse_shells).unwrap_or_else(|_| {
+        report.notes.push("/etc/shells is missing; using the getusershell() defaults /bin/sh and /bin/csh".to_string());
+        DEFAULT_SHELLS.iter().map(|s| s.to_string()).collect()
+    });
+    let defs = ctx.open("/etc/login.defs").and_then(parse_login_defs).unwrap_or_else(|_| {
+        report.notes.push("/etc/login.defs is missing; using the shadow-utils system uid range 101-999".to_string());
+        LoginDefs::default()
+    });
+    // Snapshot bundles carry configuration files only
+    let check_homes = ctx.sysroot.as_ref().is_none_or(|sysroot| sysroot.replay.is_none());
+    if !check_homes {
+        report.notes.push("home directories are not part of the snapshot and were not checked".to_string());
+    }
+
+    // Unreadable files were noted above
+    let _ = ctx.open("/etc/passwd").and_then(|reader| audit_ids(reader, "/etc/passwd", 7, &[(2, "uid"), (3, "gid")], &mut report.findings));
+    let _ = ctx.open("/etc/group").and_then(|reader| audit_ids(reader, "/etc/group", 4, &[(2, "gid")], &mut report.findings));
+    audit_users(&users, &groups, &mut report.findings);
+    audit_groups(&users, &groups, &mut report.findings);
+    audit_logins(ctx, &users, &shells, &defs, check_homes, &mut report.findings);
+    report.sort();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/bash\n\
+        deploy:x:abc:100::/home/deploy:/bin/sh\n\
+        nobody:x::65534::/nonexistent:/usr/sbin/nologin\n";
+
+    #[test]
+    fn unparsable_ids_are_not_uid0() {
+        let users = passwd::parse_passwd(PASSWD.as_bytes()).unwrap();
+        assert_eq!(users.iter().map(|u| u.name.as_str()).collect::<Vec<_>>(), ["root", "nobody"]);
+
+        let mut findings = Vec::new();
+        audit_users(&users, &[], &mut findings);
+        // An empty uid is 0 to glibc too
+        let uid0: Vec<_> = findings.iter().filter(|f| f.rule == "extra-uid0").map(|f| f.line).collect();
+        assert_eq!(uid0, [3]);
+    }
+
+    #[test]
+    fn unparsable_ids_are_reported() {
+        let mut findings = Vec::new();
+        audit_ids(PASSWD.as_bytes(), "/etc/passwd", 7, &[(2, "uid"), (3, "gid")], &mut findings).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].rule, findings[0].line), ("invalid-id", 2));
+        assert!(findings[0].message.contains("uid \"abc\""));
+
+        let mut findings = Vec::new();
+        audit_ids("wheel:x:10:root\nstaff:x:-5:\n".as_bytes(), "/etc/group", 4, &[(2, "gid")], &mut findings).unwrap();
+        assert_eq!(findings.iter().map(|f| f.line).collect::<Vec<_>>(), [2]);
+    }
+}
@@ -0,0 +1,94 @@
+use super::finding::AuditReport;
+
+/// One `<testcase>`; `failures` holds one line per reason it failed.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub classname: String,
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newline are not allowed in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// One test case per rule, failing with every finding of that rule.
+pub fn audit_cases(suite: &str, report: &AuditReport) -> Vec<TestCase> {
+    report
+        .rules
+        .iter()
+        .map(|rule| TestCase {
+            classname: suite.to_string(),
+            name: rule.id.to_string(),
+            failures: report
+                .findings
+                .iter()
+                .filter(|finding| finding.rule == rule.id)
+                .map(|finding| {
+                    let mut line = format!("{} {}: {}", finding.severity.as_str().to_uppercase(), finding.location(), finding.message);
+                    if let Some(remediation) = &finding.remediation {
+                        line.push_str(&format!(" (fix: {})", remediation));
+                    }
+                    line
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// A JUnit XML document with a single test suite; `notes` go to its
+/// `<system-out>`.
+pub fn junit_xml(suite: &str, cases: &[TestCase], notes: &[String]) -> String {
+    let failures = cases.iter().filter(|case| !case.failures.is_empty()).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        escape(env!("CARGO_PKG_NAME")),
+        cases.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+        escape(suite),
+        cases.len(),
+        failures
+    ));
+    for case in cases {
+        let open = format!("    <testcase classname=\"{}\" name=\"{}\"", escape(&case.classname), escape(&case.name));
+        match case.failures.as_slice() {
+            [] => xml.push_str(&format!("{}/>\n", open)),
+            [first, ..] => {
+                let message = if case.failures.len() == 1 {
+                    first.clone()
+                } else {
+                    format!("{} failures", case.failures.len())
+                };
+                xml.push_str(&format!("{}>\n", open));
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    escape(&message),
+                    escape(&case.failures.join("\n"))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    if !notes.is_empty() {
+        xml.push_str(&format!("    <system-out>{}</system-out>\n", escape(&notes.join("\n"))));
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
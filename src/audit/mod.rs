@@ -0,0 +1,5 @@
+pub mod dns;
+pub mod finding;
+pub mod identities;
+pub mod junit;
+pub mod sarif;
@@ -0,0 +1,88 @@
+use serde_json::{json, Value};
+
+use super::finding::{AuditReport, Finding, Severity};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The `uriBaseId` every location is relative to.
+const ROOT_BASE_ID: &str = "ROOT";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "note",
+    }
+}
+
+fn result(report: &AuditReport, finding: &Finding) -> Value {
+    let mut text = finding.message.clone();
+    if let Some(remediation) = &finding.remediation {
+        text.push_str(&format!("\n\nFix: {}", remediation));
+    }
+
+    // Paths are made relative to the root so results land on the file in a
+    // config-management checkout as well as on the audited machine
+    let mut physical = json!({
+        "artifactLocation": {
+            "uri": finding.file.trim_start_matches('/'),
+            "uriBaseId": ROOT_BASE_ID,
+        },
+    });
+    if finding.line > 0 {
+        physical["region"] = json!({ "startLine": finding.line });
+    }
+
+    let mut result = json!({
+        "ruleId": finding.rule,
+        "level": level(finding.severity),
+        "message": { "text": text },
+        "locations": [{ "physicalLocation": physical }],
+        "properties": { "severity": finding.severity.as_str() },
+    });
+    if let Some(index) = report.rules.iter().position(|rule| rule.id == finding.rule) {
+        result["ruleIndex"] = json!(index);
+    }
+    result
+}
+
+/// A SARIF 2.1.0 log with one run. `root_uri` is the `file://` URI of the
+/// directory audited as `/`, when there is one on this machine.
+pub fn sarif_log(report: &AuditReport, root_uri: Option<&str>) -> Value {
+    let rules: Vec<Value> = report
+        .rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+            })
+        })
+        .collect();
+    let notifications: Vec<Value> = report.notes.iter().map(|note| json!({ "level": "note", "message": { "text": note } })).collect();
+
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            },
+        },
+        "results": report.findings.iter().map(|finding| result(report, finding)).collect::<Vec<_>>(),
+        "invocations": [{
+            "executionSuccessful": true,
+            "toolExecutionNotifications": notifications,
+        }],
+    });
+    if let Some(uri) = root_uri {
+        run["originalUriBaseIds"] = json!({ ROOT_BASE_ID: { "uri": uri } });
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [run],
+    })
+}
@@ -0,0 +1,177 @@
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How a batch file lists names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// One name per line; blank lines and `#` comments are skipped
+    Lines,
+    /// The name column when the first row is a header naming one (`name`,
+    /// `host`, `hostname`, `user` or `group`), otherwise the first column
+    Csv,
+    /// One JSON object with a `name` field, or one JSON string, per line
+    Ndjson,
+}
+
+impl InputFormat {
+    /// The format a file's extension implies: `.csv`, `.ndjson` or `.jsonl`,
+    /// and plain lines otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => InputFormat::Csv,
+            Some("ndjson" | "jsonl") => InputFormat::Ndjson,
+            _ => InputFormat::Lines,
+        }
+    }
+}
+
+/// CSV header fields that mark the column holding names.
+const NAME_COLUMNS: [&str; 5] = ["name", "host", "hostname", "user", "group"];
+
+/// A name to resolve and the line it was read from.
+#[derive(Debug, Clone)]
+pub struct BatchName {
+    pub line: usize,
+    pub name: String,
+}
+
+/// A line that did not yield a name.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Reads every name from `reader`, in order. Lines that cannot be parsed are
+/// returned as errors in place rather than ending the batch.
+pub fn read_names<R: BufRead>(reader: R, format: InputFormat) -> io::Result<Vec<Result<BatchName, InputError>>> {
+    let mut names = Vec::new();
+    // For CSV, the column holding names once the first row has been seen
+    let mut column = None;
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (format == InputFormat::Lines && line.starts_with('#')) {
+            continue;
+        }
+        let name = match format {
+            InputFormat::Lines => Ok(line.to_string()),
+            InputFormat::Csv => {
+                let fields = split_csv(line);
+                if column.is_none() {
+                    if let Some(header) = fields.iter().position(|field| NAME_COLUMNS.iter().any(|header| field.eq_ignore_ascii_case(header))) {
+                        column = Some(header);
+                        continue;
+                    }
+                    column = Some(0);
+                }
+                match fields.into_iter().nth(column.unwrap_or_default()) {
+                    Some(field) if !field.is_empty() => Ok(field),
+                    _ => Err(format!("no value in column {}", column.unwrap_or_default() + 1)),
+                }
+            }
+            InputFormat::Ndjson => match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(serde_json::Value::String(name)) => Ok(name),
+                Ok(serde_json::Value::Object(object)) => match object.get("name") {
+                    Some(serde_json::Value::String(name)) => Ok(name.clone()),
+                    _ => Err("object has no string `name` field".to_string()),
+                },
+                Ok(_) => Err("expected an object or a string".to_string()),
+                Err(e) => Err(format!("invalid JSON: {}", e)),
+            },
+        };
+        names.push(match name {
+            Ok(name) if name.trim().is_empty() => Err(InputError {
+                line: line_number,
+                message: "empty name".to_string(),
+            }),
+            Ok(name) => Ok(BatchName {
+                line: line_number,
+                name: name.trim().to_string(),
+            }),
+            Err(message) => Err(InputError { line: line_number, message }),
+        });
+    }
+    Ok(names)
+}
+
+/// The fields of one CSV row: comma separated, optionally double-quoted
+/// with `""` for a quote. Quoted fields do not span lines.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(input: &str, format: InputFormat) -> Vec<Result<(usize, String), (usize, String)>> {
+        read_names(input.as_bytes(), format)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.map(|name| (name.line, name.name)).map_err(|error| (error.line, error.message)))
+            .collect()
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(split_csv("a, b ,c"), ["a", "b", "c"]);
+        assert_eq!(split_csv(r#""db, primary",10.0.0.5"#), ["db, primary", "10.0.0.5"]);
+        assert_eq!(split_csv(r#""say ""hi""",x"#), [r#"say "hi""#, "x"]);
+        assert_eq!(split_csv("a,,"), ["a", "", ""]);
+        assert_eq!(split_csv(""), [""]);
+    }
+
+    #[test]
+    fn lines_skip_blanks_and_comments() {
+        let read = names("web\n\n# comment\n  db  \n", InputFormat::Lines);
+        assert_eq!(read, [Ok((1, "web".to_string())), Ok((4, "db".to_string()))]);
+    }
+
+    #[test]
+    fn csv_uses_the_name_column() {
+        let read = names("env,Host\nprod,web\nstage,\n", InputFormat::Csv);
+        assert_eq!(read, [Ok((2, "web".to_string())), Err((3, "no value in column 2".to_string()))]);
+
+        // Without a header, the first column holds names
+        let read = names("web,prod\ndb,prod\n", InputFormat::Csv);
+        assert_eq!(read, [Ok((1, "web".to_string())), Ok((2, "db".to_string()))]);
+    }
+
+    #[test]
+    fn ndjson_objects_and_strings() {
+        let read = names("{\"name\":\"web\"}\n\"db\"\n{\"host\":\"x\"}\n42\nnot json\n\"  \"\n", InputFormat::Ndjson);
+        assert_eq!(read[..2], [Ok((1, "web".to_string())), Ok((2, "db".to_string()))]);
+        assert_eq!(read[2], Err((3, "object has no string `name` field".to_string())));
+        assert_eq!(read[3], Err((4, "expected an object or a string".to_string())));
+        assert!(matches!(&read[4], Err((5, message)) if message.starts_with("invalid JSON")));
+        assert_eq!(read[5], Err((6, "empty name".to_string())));
+    }
+
+    #[test]
+    fn formats_from_extensions() {
+        assert_eq!(InputFormat::from_path(Path::new("hosts.csv")), InputFormat::Csv);
+        assert_eq!(InputFormat::from_path(Path::new("users.jsonl")), InputFormat::Ndjson);
+        assert_eq!(InputFormat::from_path(Path::new("names.txt")), InputFormat::Lines);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+use super::input::{BatchName, InputError};
+use crate::context::ResolveContext;
+use crate::render::output::Database;
+use crate::render::schema::{self, LookupDocument};
+use crate::resolver::Resolver;
+
+/// The last NDJSON record of a batch.
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub database: Database,
+    /// Names read, including those that could not be parsed.
+    pub total: usize,
+    pub found: usize,
+    pub not_found: Vec<String>,
+    /// Input lines that did not yield a name.
+    pub invalid: Vec<InputError>,
+    pub duration_ms: f64,
+}
+
+impl BatchSummary {
+    pub fn failed(&self) -> usize {
+        self.not_found.len() + self.invalid.len()
+    }
+}
+
+#[derive(Serialize)]
+struct SummaryRecord<'a> {
+    summary: &'a BatchSummary,
+}
+
+/// Resolves `names` with at most `concurrency` lookups in flight, all
+/// sharing one resolver (and so one D-Bus connection) and one context,
+/// which should keep its parsed configuration
+/// ([`ResolveContext::with_parsed_config`]).
+/// Each result is written to `out` as a [`LookupDocument`] line as soon as
+/// every name before it is done, so output follows input order; the
+/// [`BatchSummary`] comes last.
+pub async fn run_batch(
+    resolver: Arc<Resolver>,
+    ctx: Arc<ResolveContext>,
+    database: Database,
+    names: Vec<Result<BatchName, InputError>>,
+    concurrency: usize,
+    out: &mut dyn Write,
+) -> io::Result<BatchSummary> {
+    let started = Instant::now();
+    let mut summary = BatchSummary {
+        database,
+        total: names.len(),
+        found: 0,
+        not_found: Vec::new(),
+        invalid: Vec::new(),
+        duration_ms: 0.0,
+    };
+    let mut pending = names.into_iter().enumerate();
+    let mut running = JoinSet::new();
+    // Finished lookups waiting for an earlier one, by input position
+    let mut done: BTreeMap<usize, Option<LookupDocument>> = BTreeMap::new();
+    let mut next = 0;
+    loop {
+        while running.len() < concurrency.max(1) {
+            let Some((position, item)) = pending.next() else {
+                break;
+            };
+            match item {
+                Ok(BatchName { name, .. }) => {
+                    let (resolver, ctx) = (resolver.clone(), ctx.clone());
+                    running.spawn(async move {
+                        let result = resolver.resolve(database, &name, &ctx, &Default::default()).await;
+                        (position, Some(LookupDocument::new(database, &result)))
+                    });
+                }
+                Err(error) => {
+                    summary.invalid.push(error);
+                    done.insert(position, None);
+                }
+            }
+        }
+        while let Some(document) = done.remove(&next) {
+            if let Some(document) = document {
+                if document.found {
+                    summary.found += 1;
+                } else {
+                    summary.not_found.push(document.name.clone());
+                }
+                writeln!(out, "{}", serde_json::to_string(&document)?)?;
+                out.flush()?;
+            }
+            next += 1;
+        }
+        match running.join_next().await {
+            Some(Ok((position, document))) => {
+                done.insert(position, document);
+            }
+            Some(Err(e)) => return Err(io::Error::other(e)),
+            None if done.is_empty() => break,
+            None => {}
+        }
+    }
+    summary.duration_ms = schema::milliseconds(started.elapsed());
+    writeln!(out, "{}", serde_json::to_string(&SummaryRecord { summary: &summary })?)?;
+    out.flush()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::dbus::SystemBus;
+    use crate::explain::decision_tree::{DecisionStep, Outcome};
+    use crate::sources::registry::Registry;
+    use crate::sources::source::{Lookup, Query, Source};
+    use crate::sysroot::Sysroot;
+
+    /// Answers after a delay that depends on the name, so lookups finish
+    /// out of input order: `slow` and `quick` are found, `gone` is not.
+    struct Delayed;
+
+    #[async_trait]
+    impl Source for Delayed {
+        fn databases(&self) -> &[Database] {
+            &[Database::Hosts]
+        }
+
+        async fn lookup(&self, query: &Query<'_>) -> Lookup {
+            let (delay, outcome) = match query.name {
+                "slow" => (80, Outcome::Match("10.0.0.1".to_string())),
+                "quick" => (0, Outcome::Match("10.0.0.2".to_string())),
+                _ => (20, Outcome::NoMatch),
+            };
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            Lookup {
+                steps: vec![DecisionStep {
+                    source: "delayed".to_string(),
+                    outcome,
+                    reason: String::new(),
+                }],
+                ..Lookup::default()
+            }
+        }
+    }
+
+    fn name(line: usize, name: &str) -> Result<BatchName, InputError> {
+        Ok(BatchName { line, name: name.to_string() })
+    }
+
+    /// A root whose nsswitch.conf sends hosts to [`Delayed`].
+    fn setup(test: &str) -> (std::path::PathBuf, Arc<Resolver>, Arc<ResolveContext>) {
+        let root = std::env::temp_dir().join(format!("resolve-batch-test-{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc/nsswitch.conf"), "hosts: delayed\n").unwrap();
+        let mut registry = Registry::default();
+        registry.register("delayed", Delayed);
+        let resolver = Arc::new(Resolver::with_bus(registry, SystemBus::default()));
+        let ctx = Arc::new(ResolveContext::from_sysroot(Sysroot::dir(&root).unwrap()).with_parsed_config());
+        (root, resolver, ctx)
+    }
+
+    fn names() -> Vec<Result<BatchName, InputError>> {
+        vec![
+            name(1, "slow"),
+            name(2, "quick"),
+            Err(InputError { line: 3, message: "empty name".to_string() }),
+            name(4, "gone"),
+            name(5, "quick"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn output_follows_input_order() {
+        let (root, resolver, ctx) = setup("order");
+        let mut out = Vec::new();
+        run_batch(resolver.clone(), ctx.clone(), Database::Hosts, names(), 4, &mut out).await.unwrap();
+
+        // The configuration was read once; later lookups do not see changes
+        std::fs::remove_file(root.join("etc/nsswitch.conf")).unwrap();
+        let again = resolver.resolve(Database::Hosts, "quick", &ctx, &Default::default()).await;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(again.result.as_deref(), Some("10.0.0.2"));
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<&str> = lines[..4].iter().map(|line| line["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["slow", "quick", "gone", "quick"]);
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[4]["summary"]["total"], 5);
+    }
+
+    #[tokio::test]
+    async fn summary_counts_every_line() {
+        let (root, resolver, ctx) = setup("summary");
+        let summary = run_batch(resolver, ctx, Database::Hosts, names(), 2, &mut Vec::new()).await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!((summary.total, summary.found), (5, 3));
+        assert_eq!(summary.not_found, ["gone"]);
+        assert_eq!(summary.invalid.len(), 1);
+        assert_eq!(summary.invalid[0].line, 3);
+        assert_eq!(summary.failed(), 2);
+    }
+}
@@ -0,0 +1,562 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::audit::finding::{AuditReport, Finding, Rule, Severity};
+use crate::audit::junit::TestCase;
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::render::output::Record;
+
+pub const RULES: [Rule; 3] = [
+    Rule { id: "host-expectation", description: "Host names resolve as the expectations file requires" },
+    Rule { id: "user-expectation", description: "Users resolve as the expectations file requires" },
+    Rule { id: "group-expectation", description: "Groups resolve as the expectations file requires" },
+];
+
+fn resolves_by_default() -> bool {
+    true
+}
+
+/// `[[host]]`: the name resolves (or not), optionally to `address` from `source`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostExpectation {
+    pub name: String,
+    pub address: Option<String>,
+    pub source: Option<String>,
+    #[serde(default = "resolves_by_default")]
+    pub resolves: bool,
+}
+
+/// `[[user]]`: the user resolves (or not), optionally to `uid` from `source`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserExpectation {
+    pub name: String,
+    pub uid: Option<u32>,
+    pub source: Option<String>,
+    #[serde(default = "resolves_by_default")]
+    pub resolves: bool,
+}
+
+/// `[[group]]`: the group resolves (or not), optionally to `gid` from
+/// `source`, and lists at least `members`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupExpectation {
+    pub name: String,
+    pub gid: Option<u32>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    pub source: Option<String>,
+    #[serde(default = "resolves_by_default")]
+    pub resolves: bool,
+}
+
+/// The expectations file in TOML, which records where each table starts.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectationFile {
+    #[serde(default)]
+    host: Vec<Spanned<HostExpectation>>,
+    #[serde(default)]
+    user: Vec<Spanned<UserExpectation>>,
+    #[serde(default)]
+    group: Vec<Spanned<GroupExpectation>>,
+}
+
+/// The expectations file in YAML: `host`, `user` and `group` sequences.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct YamlExpectationFile {
+    #[serde(default)]
+    host: Vec<HostExpectation>,
+    #[serde(default)]
+    user: Vec<UserExpectation>,
+    #[serde(default)]
+    group: Vec<GroupExpectation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Located<T> {
+    pub line: usize,
+    pub expectation: T,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expectations {
+    pub path: String,
+    pub hosts: Vec<Located<HostExpectation>>,
+    pub users: Vec<Located<UserExpectation>>,
+    pub groups: Vec<Located<GroupExpectation>>,
+}
+
+fn locate<T>(content: &str, spanned: Vec<Spanned<T>>) -> Vec<Located<T>> {
+    spanned
+        .into_iter()
+        .map(|item| Located {
+            line: content[..item.span().start].matches('\n').count() + 1,
+            expectation: item.into_inner(),
+        })
+        .collect()
+}
+
+/// The line of each item of the top-level sequence `key`, for block-style
+/// YAML; serde_yaml does not report positions. Items it cannot place (flow
+/// style) get the line of `key`.
+fn yaml_lines<T>(content: &str, key: &str, items: Vec<T>) -> Vec<Located<T>> {
+    let mut key_line = 1;
+    let mut lines = Vec::new();
+    let mut in_section = false;
+    let mut item_indent = None;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if trimmed == "-" || trimmed.starts_with("- ") {
+            if in_section && item_indent.is_none_or(|item_indent| item_indent == indent) {
+                item_indent = Some(indent);
+                lines.push(index + 1);
+            }
+        } else if indent == 0 {
+            in_section = trimmed.strip_prefix(key).is_some_and(|rest| rest.starts_with(':'));
+            item_indent = None;
+            if in_section {
+                key_line = index + 1;
+            }
+        }
+    }
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, expectation)| Located {
+            line: lines.get(index).copied().unwrap_or(key_line),
+            expectation,
+        })
+        .collect()
+}
+
+/// Parses an expectations file: YAML when `path` ends in `.yaml` or `.yml`,
+/// TOML otherwise.
+pub fn parse_expectations(content: &str, path: &str) -> io::Result<Expectations> {
+    let invalid = |e: &dyn std::fmt::Display| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e));
+    if matches!(Path::new(path).extension().and_then(|ext| ext.to_str()), Some("yaml" | "yml")) {
+        let file: YamlExpectationFile = serde_yaml::from_str(content).map_err(|e| invalid(&e))?;
+        return Ok(Expectations {
+            path: path.to_string(),
+            hosts: yaml_lines(content, "host", file.host),
+            users: yaml_lines(content, "user", file.user),
+            groups: yaml_lines(content, "group", file.group),
+        });
+    }
+    let file: ExpectationFile = toml::from_str(content).map_err(|e| invalid(&e))?;
+    Ok(Expectations {
+        path: path.to_string(),
+        hosts: locate(content, file.host),
+        users: locate(content, file.user),
+        groups: locate(content, file.group),
+    })
+}
+
+pub fn load_expectations<P: AsRef<Path>>(path: P) -> io::Result<Expectations> {
+    let path = path.as_ref();
+    parse_expectations(&fs::read_to_string(path)?, &path.display().to_string())
+}
+
+/// The outcome of one expectation, with the trace that produced the answer.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub database: &'static str,
+    pub name: String,
+    pub line: usize,
+    pub expected: String,
+    pub actual: Option<String>,
+    /// The nsswitch source the answer came from.
+    pub source: Option<String>,
+    pub failures: Vec<String>,
+    pub steps: Vec<DecisionStep>,
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    pub file: String,
+    pub results: Vec<CheckResult>,
+}
+
+/// The nsswitch source name of a step, e.g. `files` for "files (/etc/hosts)".
+fn step_source(step: &DecisionStep) -> &str {
+    step.source.split_whitespace().next().unwrap_or_default()
+}
+
+fn describe_outcome(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Match(value) => format!("matched {}", value),
+        Outcome::NoMatch => "no match".to_string(),
+        Outcome::Error(e) => format!("error: {}", e),
+        Outcome::Unsupported(why) => format!("unavailable: {}", why),
+        Outcome::TryAgain(why) => format!("temporarily unavailable: {}", why),
+        Outcome::Info(info) => info.clone(),
+    }
+}
+
+/// The step that produced `result`: the last match with that value.
+fn answering_step<'a>(result: Option<&str>, steps: &'a [DecisionStep]) -> Option<&'a DecisionStep> {
+    let result = result?;
+    steps.iter().rev().find(|step| matches!(&step.outcome, Outcome::Match(value) if value == result))
+}
+
+/// Every source that was consulted and what it returned.
+fn summarize(steps: &[DecisionStep]) -> String {
+    let consulted: Vec<String> = steps
+        .iter()
+        .filter(|step| !matches!(step.outcome, Outcome::Info(_)))
+        .map(|step| format!("{} gave {}", step.source, describe_outcome(&step.outcome)))
+        .collect();
+    if consulted.is_empty() {
+        "no source was consulted".to_string()
+    } else {
+        consulted.join(", ")
+    }
+}
+
+/// Why `source` did not provide the answer, from its own steps in the trace.
+fn explain_source(source: &str, steps: &[DecisionStep]) -> String {
+    let own: Vec<String> = steps
+        .iter()
+        .filter(|step| step_source(step) == source)
+        .map(|step| format!("{} gave {} ({})", step.source, describe_outcome(&step.outcome), step.reason))
+        .collect();
+    if !own.is_empty() {
+        return own.join("; ");
+    }
+    // The walk stopped before reaching the source, or it is not configured
+    match steps.iter().rev().find(|step| step.source.starts_with("nsswitch (")) {
+        Some(step) => format!("{} was never consulted: {}", source, step.reason),
+        None => format!(
+            "{} was never consulted; the lookup ended before reaching it, or it is not listed for this database in nsswitch.conf",
+            source
+        ),
+    }
+}
+
+/// What every kind of expectation shares: whether the name resolves, to
+/// which value, and from which source.
+struct Claim<'a> {
+    database: &'static str,
+    name: &'a str,
+    line: usize,
+    resolves: bool,
+    value: Option<String>,
+    source: Option<&'a str>,
+}
+
+fn evaluate(claim: Claim, result: Option<&str>, steps: &[DecisionStep]) -> CheckResult {
+    let Claim {
+        database,
+        name,
+        line,
+        resolves,
+        value,
+        source,
+    } = claim;
+    let what = match database {
+        "hosts" => "address",
+        "passwd" => "uid",
+        _ => "gid",
+    };
+    let mut expected = if resolves { "resolves".to_string() } else { "does not resolve".to_string() };
+    if let Some(value) = &value {
+        expected.push_str(&format!(" to {} {}", what, value));
+    }
+    if let Some(source) = source {
+        expected.push_str(&format!(" via {}", source));
+    }
+
+    let answered_by = answering_step(result, steps).map(step_source).map(String::from);
+    let mut failures = Vec::new();
+    match (resolves, result) {
+        (true, None) => failures.push(match source {
+            Some(source) => format!("{} did not resolve; {}", name, explain_source(source, steps)),
+            None => format!("{} did not resolve; {}", name, summarize(steps)),
+        }),
+        (false, Some(actual)) => failures.push(format!(
+            "{} resolved to {} via {}",
+            name,
+            actual,
+            answered_by.as_deref().unwrap_or("an unknown source")
+        )),
+        (true, Some(actual)) => {
+            if let Some(value) = value.as_deref().filter(|value| *value != actual) {
+                failures.push(format!("{} resolved to {} {} instead of {}", name, what, actual, value));
+            }
+            if let Some(source) = source.filter(|source| answered_by.as_deref() != Some(*source)) {
+                failures.push(format!(
+                    "{} answered instead of {}; {}",
+                    answered_by.as_deref().unwrap_or("an unknown source"),
+                    source,
+                    explain_source(source, steps)
+                ));
+            }
+        }
+        (false, None) => {}
+    }
+
+    CheckResult {
+        database,
+        name: name.to_string(),
+        line,
+        expected,
+        actual: result.map(String::from),
+        source: answered_by,
+        failures,
+        steps: steps.to_vec(),
+    }
+}
+
+pub fn check_host(located: &Located<HostExpectation>, result: Option<&str>, steps: &[DecisionStep]) -> CheckResult {
+    let e = &located.expectation;
+    let claim = Claim {
+        database: "hosts",
+        name: &e.name,
+        line: located.line,
+        resolves: e.resolves,
+        value: e.address.clone(),
+        source: e.source.as_deref(),
+    };
+    evaluate(claim, result, steps)
+}
+
+pub fn check_user(located: &Located<UserExpectation>, result: Option<&str>, steps: &[DecisionStep]) -> CheckResult {
+    let e = &located.expectation;
+    let claim = Claim {
+        database: "passwd",
+        name: &e.name,
+        line: located.line,
+        resolves: e.resolves,
+        value: e.uid.map(|uid| uid.to_string()),
+        source: e.source.as_deref(),
+    };
+    evaluate(claim, result, steps)
+}
+
+/// `record` is the entry the answering source returned, when it provides
+/// one; member expectations are checked against it.
+pub fn check_group(
+    located: &Located<GroupExpectation>,
+    result: Option<&str>,
+    steps: &[DecisionStep],
+    record: Option<&Record>,
+) -> CheckResult {
+    let e = &located.expectation;
+    let claim = Claim {
+        database: "group",
+        name: &e.name,
+        line: located.line,
+        resolves: e.resolves,
+        value: e.gid.map(|gid| gid.to_string()),
+        source: e.source.as_deref(),
+    };
+    let mut checked = evaluate(claim, result, steps);
+    if e.members.is_empty() || result.is_none() {
+        return checked;
+    }
+    checked.expected.push_str(&format!(" with members {}", e.members.join(", ")));
+    let members = match record {
+        Some(Record::Group(entry)) => Some(&entry.members),
+        _ => None,
+    };
+    match members {
+        Some(members) => {
+            let missing: Vec<&str> = e.members.iter().filter(|m| !members.contains(m)).map(String::as_str).collect();
+            if !missing.is_empty() {
+                checked.failures.push(format!(
+                    "{} does not list {} (members: {})",
+                    e.name,
+                    missing.join(", "),
+                    if members.is_empty() { "none".to_string() } else { members.join(", ") }
+                ));
+            }
+        }
+        None => checked.failures.push(format!(
+            "members of {} cannot be read from {}",
+            e.name,
+            checked.source.as_deref().unwrap_or("an unknown source")
+        )),
+    }
+    checked
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(CheckResult::passed)
+    }
+
+    fn rule(result: &CheckResult) -> &'static str {
+        match result.database {
+            "hosts" => RULES[0].id,
+            "passwd" => RULES[1].id,
+            _ => RULES[2].id,
+        }
+    }
+
+    /// Failed expectations as findings at their line in the expectations
+    /// file, for the SARIF and JSON-compatible audit outputs.
+    pub fn to_audit_report(&self) -> AuditReport {
+        let findings = self
+            .results
+            .iter()
+            .filter(|result| !result.passed())
+            .map(|result| Finding {
+                rule: Self::rule(result),
+                severity: Severity::High,
+                file: self.file.clone(),
+                line: result.line,
+                message: format!("expected {} {} {}: {}", result.database, result.name, result.expected, result.failures.join("; ")),
+                remediation: None,
+            })
+            .collect();
+        AuditReport {
+            findings,
+            notes: Vec::new(),
+            rules: &RULES,
+        }
+    }
+
+    /// One test case per expectation.
+    pub fn junit_cases(&self, suite: &str) -> Vec<TestCase> {
+        self.results
+            .iter()
+            .map(|result| TestCase {
+                classname: format!("{}.{}", suite, result.database),
+                name: format!("{} {}", result.name, result.expected),
+                failures: result.failures.iter().map(|failure| format!("{}:{}: {}", self.file, result.line, failure)).collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nss::group::GroupEntry;
+
+    fn step(source: &str, outcome: Outcome) -> DecisionStep {
+        DecisionStep {
+            source: source.to_string(),
+            outcome,
+            reason: String::new(),
+        }
+    }
+
+    fn claim(resolves: bool, value: Option<&str>, source: Option<&'static str>) -> Claim<'static> {
+        Claim {
+            database: "hosts",
+            name: "db.internal",
+            line: 3,
+            resolves,
+            value: value.map(String::from),
+            source,
+        }
+    }
+
+    #[test]
+    fn evaluate_passes_matching_answer() {
+        let steps = [step("files (/etc/hosts)", Outcome::Match("10.0.0.5".to_string()))];
+        let result = evaluate(claim(true, Some("10.0.0.5"), Some("files")), Some("10.0.0.5"), &steps);
+        assert!(result.passed(), "{:?}", result.failures);
+        assert_eq!(result.expected, "resolves to address 10.0.0.5 via files");
+        assert_eq!(result.source.as_deref(), Some("files"));
+    }
+
+    #[test]
+    fn evaluate_reports_wrong_value_and_source() {
+        let steps = [
+            step("files (/etc/hosts)", Outcome::NoMatch),
+            step("dns (10.0.0.53)", Outcome::Match("10.0.0.6".to_string())),
+        ];
+        let result = evaluate(claim(true, Some("10.0.0.5"), Some("files")), Some("10.0.0.6"), &steps);
+        assert_eq!(result.failures.len(), 2);
+        assert_eq!(result.failures[0], "db.internal resolved to address 10.0.0.6 instead of 10.0.0.5");
+        assert!(result.failures[1].starts_with("dns answered instead of files; files (/etc/hosts) gave no match"));
+    }
+
+    #[test]
+    fn evaluate_explains_missing_answers() {
+        let steps = [step("files (/etc/hosts)", Outcome::NoMatch)];
+        let result = evaluate(claim(true, None, None), None, &steps);
+        assert_eq!(result.failures, ["db.internal did not resolve; files (/etc/hosts) gave no match"]);
+
+        let result = evaluate(claim(true, None, Some("dns")), None, &steps);
+        assert!(result.failures[0].contains("dns was never consulted"));
+
+        assert!(evaluate(claim(false, None, None), None, &steps).passed());
+        let steps = [step("files (/etc/hosts)", Outcome::Match("10.0.0.5".to_string()))];
+        let result = evaluate(claim(false, None, None), Some("10.0.0.5"), &steps);
+        assert_eq!(result.failures, ["db.internal resolved to 10.0.0.5 via files"]);
+    }
+
+    #[test]
+    fn toml_and_yaml_agree() {
+        let toml = "[[host]]\nname = \"db.internal\"\naddress = \"10.0.0.5\"\n\n[[user]]\nname = \"deploy\"\nuid = 1500\n";
+        let yaml = "# checks\nhost:\n  - name: db.internal\n    address: 10.0.0.5\nuser:\n- name: deploy\n  uid: 1500\n- name: ci\n  resolves: false\n";
+        let toml = parse_expectations(toml, "expectations.toml").unwrap();
+        let yaml = parse_expectations(yaml, "expectations.yml").unwrap();
+        assert_eq!(toml.hosts[0].expectation.address.as_deref(), Some("10.0.0.5"));
+        assert_eq!(yaml.hosts[0].expectation.address.as_deref(), Some("10.0.0.5"));
+        assert_eq!((toml.hosts[0].line, toml.users[0].line), (1, 5));
+        assert_eq!((yaml.hosts[0].line, yaml.users[0].line, yaml.users[1].line), (3, 6, 8));
+        assert_eq!(yaml.users[0].expectation.uid, Some(1500));
+        assert!(!yaml.users[1].expectation.resolves);
+
+        assert!(parse_expectations("host:\n  - name: a\n    port: 1\n", "e.yaml").is_err());
+        assert!(parse_expectations("host:\n  - name: a\n", "e.toml").is_err());
+    }
+
+    #[test]
+    fn group_members_come_from_the_answering_source() {
+        let located = Located {
+            line: 1,
+            expectation: GroupExpectation {
+                name: "wheel".to_string(),
+                gid: Some(10),
+                members: vec!["deploy".to_string()],
+                source: Some("mymachines".to_string()),
+                resolves: true,
+            },
+        };
+        let steps = [
+            step("files (/etc/group)", Outcome::NoMatch),
+            step("mymachines", Outcome::Match("10".to_string())),
+        ];
+        let entry = |members: &[&str]| {
+            Record::Group(GroupEntry {
+                name: "wheel".to_string(),
+                password: "x".to_string(),
+                gid: 10,
+                members: members.iter().map(|m| m.to_string()).collect(),
+                source: "mymachines".to_string(),
+                line: 0,
+            })
+        };
+
+        let result = check_group(&located, Some("10"), &steps, Some(&entry(&["root", "deploy"])));
+        assert!(result.passed(), "{:?}", result.failures);
+
+        let result = check_group(&located, Some("10"), &steps, Some(&entry(&["root"])));
+        assert_eq!(result.failures, ["wheel does not list deploy (members: root)"]);
+
+        let result = check_group(&located, Some("10"), &steps, None);
+        assert_eq!(result.failures, ["members of wheel cannot be read from mymachines"]);
+    }
+}
@@ -1,11 +1,42 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use resolve::batch::input::InputFormat;
+use resolve::render::renderer::OutputFormat;
 
 #[derive(Parser)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+    /// Shorthand for `--format json`
     #[arg(global = true, long)]
     pub json: bool,
+    /// How results are printed; the default is color on a terminal and plain otherwise
+    #[arg(global = true, long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Search this directory for `resolve-source-<name>` plugins; repeatable,
+    /// defaults to $RESOLVE_PLUGIN_PATH
+    #[arg(global = true, long, value_name = "DIR", value_delimiter = ':')]
+    pub plugin_dir: Vec<PathBuf>,
+    #[command(flatten)]
+    pub target: TargetArgs,
+}
+
+/// Which system a lookup runs against; the default is the running host.
+#[derive(Args)]
+pub struct TargetArgs {
+    /// Resolve as a process in this `ip netns` namespace would
+    #[arg(global = true, long)]
+    pub netns: Option<String>,
+    /// Resolve inside a container image (OCI layout directory, OCI archive or `docker save` tarball)
+    #[arg(global = true, long, conflicts_with = "netns")]
+    pub image: Option<PathBuf>,
+    /// Resolve against this directory as `/` (a mounted disk image, chroot or fixture tree)
+    #[arg(global = true, long, conflicts_with_all = ["netns", "image"])]
+    pub root: Option<PathBuf>,
+    /// Replay a bundle written by `resolve snapshot`
+    #[arg(global = true, long, conflicts_with_all = ["netns", "image", "root"])]
+    pub from_snapshot: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -16,6 +47,15 @@ pub enum Command {
         pid: Option<u32>,
         #[arg(long)]
         why: bool,
+        /// Consult every source, reporting those after the decision as shadowed
+        #[arg(long)]
+        all_sources: bool,
+        /// Exit with status 1 unless the answer is this address
+        #[arg(long, value_name = "ADDRESS")]
+        expect: Option<String>,
+        /// Consult every source and explain why the --expect address was not the answer
+        #[arg(long, requires = "expect")]
+        why_not: bool,
     },
     User {
         name: String,
@@ -23,6 +63,9 @@ pub enum Command {
         pid: Option<u32>,
         #[arg(long)]
         why: bool,
+        /// Consult every source, reporting those after the decision as shadowed
+        #[arg(long)]
+        all_sources: bool,
     },
     Group {
         name: String,
@@ -30,11 +73,82 @@ pub enum Command {
         pid: Option<u32>,
         #[arg(long)]
         why: bool,
+        /// Consult every source, reporting those after the decision as shadowed
+        #[arg(long)]
+        all_sources: bool,
+    },
+    /// Capture the files and live answers resolution depends on into a bundle
+    Snapshot {
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long)]
+        pid: Option<u32>,
+        /// Host names whose live answers (DNS, mDNS, machined) are recorded
+        #[arg(long)]
+        host: Vec<String>,
+        /// User names whose live answers are recorded
+        #[arg(long)]
+        user: Vec<String>,
+        /// Group names whose live answers are recorded
+        #[arg(long)]
+        group: Vec<String>,
+    },
+    /// Check identity and resolver configuration for problems
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+    /// Assert how names must resolve, from a TOML or YAML expectations file
+    Check {
+        /// Read as YAML when it ends in .yaml or .yml, as TOML otherwise
+        file: PathBuf,
+        #[arg(long)]
+        pid: Option<u32>,
+    },
+    /// Resolve many names concurrently, printing one NDJSON lookup per name and a summary
+    Batch {
+        /// What the names are
+        #[arg(long = "type", value_enum)]
+        kind: BatchType,
+        /// File of names; standard input when omitted or `-`
+        file: Option<PathBuf>,
+        /// How names are listed; the default follows the file extension, and plain lines for standard input
+        #[arg(long, value_enum)]
+        input: Option<InputFormat>,
+        /// Lookups in flight at once
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        #[arg(long)]
+        pid: Option<u32>,
     },
+    /// Print the JSON Schema of lookup output (`--format json`)
+    Schema,
     Diff {
         #[arg(long)]
         pid: u32,
         #[arg(long)]
         pid2: u32,
     },
-}
\ No newline at end of file
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommand {
+    /// Accounts and groups in /etc/passwd and /etc/group
+    Identities {
+        #[arg(long)]
+        pid: Option<u32>,
+    },
+    /// /etc/hosts, resolv.conf, nsswitch.conf and systemd-resolved settings
+    Dns {
+        #[arg(long)]
+        pid: Option<u32>,
+    },
+}
+
+/// `resolve batch --type`.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BatchType {
+    Host,
+    User,
+    Group,
+}
@@ -0,0 +1,247 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use crate::dns::resolv_conf::{self, ResolvConf};
+use crate::nss::group::{self, GroupEntry};
+use crate::nss::hosts::{self, HostEntry};
+use crate::nss::nsswitch::{self, NssOrder};
+use crate::nss::passwd::{self, UserEntry};
+use crate::proc;
+use crate::sysroot::{FileMeta, Sysroot};
+
+/// A parse result that can be handed out repeatedly, as `io::Error` cannot
+/// be cloned.
+type Parsed<T> = Result<Arc<T>, (io::ErrorKind, String)>;
+
+/// Configuration files parsed on first use and kept, so a batch reads
+/// nsswitch.conf, hosts, passwd, group and resolv.conf once rather than once
+/// per name.
+#[derive(Debug, Default)]
+pub struct ParsedConfig {
+    nsswitch: OnceLock<Parsed<NssOrder>>,
+    hosts: OnceLock<Parsed<Vec<HostEntry>>>,
+    passwd: OnceLock<Parsed<Vec<UserEntry>>>,
+    group: OnceLock<Parsed<Vec<GroupEntry>>>,
+    resolv_conf: OnceLock<Parsed<ResolvConf>>,
+}
+
+/// The view of the system a lookup runs against: the host's own files, the
+/// root of another process when `--pid` is given, or the /etc/netns overlay
+/// of a named network namespace with `--netns`, or a directory (`--root`) or
+/// container image (`--image`) standing in for `/`.
+#[derive(Debug, Clone)]
+pub struct ResolveContext {
+    pub pid: Option<u32>,
+    pub netns: Option<proc::namespaces::NamedNetns>,
+    pub container: Option<proc::container::ContainerInfo>,
+    pub pod: Option<proc::kubernetes::PodInfo>,
+    pub userns: Option<proc::userns::UserNamespace>,
+    pub sysroot: Option<Sysroot>,
+    /// Shared by clones, so every lookup of a batch sees the same parse.
+    config: Option<Arc<ParsedConfig>>,
+}
+
+impl ResolveContext {
+    pub fn new(pid: Option<u32>, netns: Option<String>) -> Self {
+        let netns = match netns {
+            Some(name) => Some(proc::namespaces::named_netns(&name, pid)),
+            None => pid.and_then(proc::namespaces::named_netns_for_pid),
+        };
+        let mut ctx = ResolveContext {
+            pid,
+            netns,
+            container: pid.and_then(proc::container::detect_container),
+            pod: None,
+            userns: pid.and_then(proc::userns::user_namespace),
+            sysroot: None,
+            config: None,
+        };
+        ctx.pod = proc::kubernetes::detect_pod(&ctx);
+        ctx
+    }
+
+    /// Resolves against a file tree alone, with no running system behind it.
+    pub fn from_sysroot(sysroot: Sysroot) -> Self {
+        let mut ctx = ResolveContext {
+            pid: None,
+            netns: None,
+            container: None,
+            pod: None,
+            userns: None,
+            sysroot: Some(sysroot),
+            config: None,
+        };
+        ctx.pod = proc::kubernetes::detect_pod(&ctx);
+        ctx
+    }
+
+    /// What the lookup ran against, for provenance: the sysroot label,
+    /// "pid 42 (container web)", "netns blue" or "local system".
+    pub fn label(&self) -> String {
+        if let Some(sysroot) = &self.sysroot {
+            return sysroot.label.clone();
+        }
+        let mut label = match self.pid {
+            Some(pid) => format!("pid {}", pid),
+            None => "local system".to_string(),
+        };
+        if let Some(container) = &self.container {
+            label.push_str(&format!(" (container {})", container.label()));
+        }
+        if let Some(netns) = &self.netns {
+            label = match self.pid {
+                Some(_) => format!("{}, netns {}", label, netns.name),
+                None => format!("netns {}", netns.name),
+            };
+        }
+        label
+    }
+
+    /// Whether sources that talk to the running system (DNS servers, D-Bus,
+    /// multicast) can be consulted.
+    pub fn is_live(&self) -> bool {
+        self.sysroot.is_none()
+    }
+
+    fn netns_override(&self, path: &str) -> Option<&proc::namespaces::NetnsOverride> {
+        self.netns.as_ref()?.overrides.iter().find(|o| o.target == path)
+    }
+
+    /// The time, in seconds since the epoch, that time-dependent state such
+    /// as DHCP lease expiry is judged at: when a snapshot was captured while
+    /// replaying one, otherwise now.
+    pub fn now(&self) -> i64 {
+        if let Some(replay) = self.sysroot.as_ref().and_then(|sysroot| sysroot.replay.as_ref()) {
+            return replay.manifest.created as i64;
+        }
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+
+    /// Where `path` (as seen by the target process) can be read from here.
+    fn path(&self, path: &str) -> PathBuf {
+        match self.pid {
+            // The process's own root already shows any netns bind mounts
+            Some(pid) => PathBuf::from(proc::namespaces::get_proc_root_path(pid, path)),
+            None => match self.netns_override(path) {
+                Some(netns_override) => PathBuf::from(&netns_override.source),
+                None => PathBuf::from(path),
+            },
+        }
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        match &self.sysroot {
+            Some(sysroot) => sysroot.read_to_string(path),
+            None => fs::read_to_string(self.path(path)),
+        }
+    }
+
+    /// Opens `path` for the line-based parsers.
+    pub fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        match &self.sysroot {
+            Some(sysroot) => Ok(Box::new(io::Cursor::new(sysroot.read(path)?))),
+            None => Ok(Box::new(io::BufReader::new(fs::File::open(self.path(path))?))),
+        }
+    }
+
+    /// Keeps the configuration files this context parses for every later
+    /// lookup through it or its clones. Changes to the files are not seen
+    /// afterwards, which suits a batch but not a long-lived context.
+    pub fn with_parsed_config(mut self) -> Self {
+        self.config = Some(Arc::default());
+        self
+    }
+
+    fn parsed<T>(
+        &self,
+        slot: fn(&ParsedConfig) -> &OnceLock<Parsed<T>>,
+        path: &str,
+        parse: fn(Box<dyn BufRead>) -> io::Result<T>,
+    ) -> io::Result<Arc<T>> {
+        let read = || self.open(path).and_then(parse).map(Arc::new).map_err(|e| (e.kind(), e.to_string()));
+        let parsed = match &self.config {
+            Some(config) => slot(config).get_or_init(read).clone(),
+            None => read(),
+        };
+        parsed.map_err(|(kind, message)| io::Error::new(kind, message))
+    }
+
+    pub fn nsswitch(&self) -> io::Result<Arc<NssOrder>> {
+        self.parsed(|config| &config.nsswitch, "/etc/nsswitch.conf", nsswitch::parse_nsswitch)
+    }
+
+    pub fn hosts(&self) -> io::Result<Arc<Vec<HostEntry>>> {
+        self.parsed(|config| &config.hosts, "/etc/hosts", hosts::parse_hosts)
+    }
+
+    pub fn passwd(&self) -> io::Result<Arc<Vec<UserEntry>>> {
+        self.parsed(|config| &config.passwd, "/etc/passwd", passwd::parse_passwd)
+    }
+
+    pub fn group(&self) -> io::Result<Arc<Vec<GroupEntry>>> {
+        self.parsed(|config| &config.group, "/etc/group", group::parse_group)
+    }
+
+    pub fn resolv_conf(&self) -> io::Result<Arc<ResolvConf>> {
+        self.parsed(|config| &config.resolv_conf, "/etc/resolv.conf", resolv_conf::parse_resolv_conf)
+    }
+
+    /// Entry names in a directory, sorted.
+    pub fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        if let Some(sysroot) = &self.sysroot {
+            return sysroot.read_dir(path);
+        }
+        let mut names: Vec<String> = fs::read_dir(self.path(path))?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn metadata(&self, path: &str) -> io::Result<FileMeta> {
+        match &self.sysroot {
+            Some(sysroot) => sysroot.metadata(path),
+            None => Ok(fs::metadata(self.path(path))?.into()),
+        }
+    }
+
+    /// Names `path` for explanations, including who put it there.
+    pub fn describe(&self, path: &str) -> String {
+        if let Some(sysroot) = &self.sysroot {
+            return format!("{} in {}", path, sysroot.label);
+        }
+        if let (Some(netns), Some(netns_override)) = (&self.netns, self.netns_override(path)) {
+            return if netns_override.applied {
+                format!("{} over {} (ip netns {})", netns_override.source, path, netns.name)
+            } else {
+                format!("{}, {} exists but is not mounted in this process", path, netns_override.source)
+            };
+        }
+        let injected = self
+            .container
+            .as_ref()
+            .and_then(|container| Some((container, container.injected_file(path)?)));
+        if let Some((container, file)) = injected {
+            return format!(
+                "{}, injected by {} for container {} from {}",
+                path,
+                container.runtime.as_str(),
+                container.label(),
+                file.host_source
+            );
+        }
+        match (&self.pod, path) {
+            (Some(pod), "/etc/hosts") if pod.kubelet_hosts => {
+                format!("{}, managed by kubelet for pod {}", path, pod.name.as_deref().unwrap_or("(unknown)"))
+            }
+            (Some(pod), "/etc/resolv.conf") => format!("{}, dnsPolicy {}", path, pod.dns_policy.as_str()),
+            _ => path.to_string(),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use tokio::sync::OnceCell;
+use zbus::Connection;
+
+/// Lazily opened system bus connection shared by every D-Bus backed source
+/// (systemd-resolved, systemd-machined) within one invocation.
+#[derive(Default)]
+pub struct SystemBus {
+    connection: OnceCell<Connection>,
+}
+
+impl SystemBus {
+    /// Uses `connection` instead of the system bus, such as a session bus
+    /// or a peer-to-peer stand-in for the services sources talk to.
+    pub fn with_connection(connection: Connection) -> Self {
+        SystemBus {
+            connection: OnceCell::new_with(Some(connection)),
+        }
+    }
+
+    pub async fn connection(&self) -> zbus::Result<&Connection> {
+        self.connection.get_or_try_init(Connection::system).await
+    }
+}
+
+/// True when a method call failed with the given D-Bus error name.
+pub fn is_method_error(error: &zbus::Error, name: &str) -> bool {
+    matches!(error, zbus::Error::MethodError(error_name, _, _) if error_name.as_str() == name)
+}
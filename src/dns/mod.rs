@@ -1,2 +1,6 @@
+pub mod names;
+pub mod nameserver;
+pub mod numeric;
 pub mod resolv_conf;
-pub mod resolved;
\ No newline at end of file
+pub mod resolved;
+pub mod wire;
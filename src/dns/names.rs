@@ -0,0 +1,171 @@
+#[derive(Debug, Clone)]
+pub struct NormalizedName {
+    pub ascii: String,
+    pub idna_applied: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialUseDomain {
+    Localhost,
+    Invalid,
+    Test,
+    Onion,
+    Local,
+    HomeArpa,
+}
+
+impl SpecialUseDomain {
+    pub fn zone(&self) -> &'static str {
+        match self {
+            SpecialUseDomain::Localhost => "localhost",
+            SpecialUseDomain::Invalid => "invalid",
+            SpecialUseDomain::Test => "test",
+            SpecialUseDomain::Onion => "onion",
+            SpecialUseDomain::Local => "local",
+            SpecialUseDomain::HomeArpa => "home.arpa",
+        }
+    }
+
+    pub fn rfc(&self) -> &'static str {
+        match self {
+            SpecialUseDomain::Localhost | SpecialUseDomain::Invalid | SpecialUseDomain::Test => "RFC 6761",
+            SpecialUseDomain::Onion => "RFC 7686",
+            SpecialUseDomain::Local => "RFC 6762",
+            SpecialUseDomain::HomeArpa => "RFC 8375",
+        }
+    }
+
+    /// How the common resolver stacks treat names under this zone.
+    pub fn stack_behavior(&self) -> &'static str {
+        match self {
+            SpecialUseDomain::Localhost => {
+                "systemd-resolved and nss-myhostname synthesize 127.0.0.1/::1 without sending queries; \
+                 plain glibc only answers if /etc/hosts lists the name, otherwise it goes to unicast DNS"
+            }
+            SpecialUseDomain::Invalid => {
+                "systemd-resolved refuses to resolve it; glibc and other stub resolvers forward it and \
+                 rely on the root servers returning NXDOMAIN"
+            }
+            SpecialUseDomain::Test => {
+                "No resolver special-cases it; queries go to unicast DNS and only succeed if a local zone serves .test"
+            }
+            SpecialUseDomain::Onion => {
+                "systemd-resolved refuses to resolve it; glibc forwards it to unicast DNS, leaking the name \
+                 unless a Tor-aware resolver intercepts it"
+            }
+            SpecialUseDomain::Local => {
+                "Resolved by multicast DNS: nss-mdns (mdns*_minimal, mdns*) or systemd-resolved with MulticastDNS= \
+                 enabled on a link; without either it falls through to unicast DNS, which is why results differ between machines"
+            }
+            SpecialUseDomain::HomeArpa => {
+                "Routed to unicast DNS only; systemd-resolved treats it as a private zone and skips DNSSEC \
+                 validation, so answers come from the local network's resolver"
+            }
+        }
+    }
+}
+
+fn ends_with_zone(name: &str, zone: &str) -> bool {
+    name == zone || name.ends_with(&format!(".{}", zone))
+}
+
+pub fn special_use_domain(name: &str) -> Option<SpecialUseDomain> {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    [
+        SpecialUseDomain::Localhost,
+        SpecialUseDomain::Invalid,
+        SpecialUseDomain::Test,
+        SpecialUseDomain::Onion,
+        SpecialUseDomain::Local,
+        SpecialUseDomain::HomeArpa,
+    ]
+    .into_iter()
+    .find(|domain| ends_with_zone(&name, domain.zone()))
+}
+
+/// Converts a host name to the ASCII form that getaddrinfo with AI_IDN sends
+/// to NSS, rejecting names that cannot be encoded as DNS labels.
+pub fn normalize_host_name(name: &str) -> Result<NormalizedName, String> {
+    let idna_applied = !name.is_ascii();
+    let ascii = if idna_applied {
+        idna::domain_to_ascii(name).map_err(|e| format!("IDNA conversion failed: {}", e))?
+    } else {
+        name.to_string()
+    };
+
+    let labels = ascii.strip_suffix('.').unwrap_or(&ascii);
+    if labels.is_empty() {
+        return Err("Empty host name".to_string());
+    }
+    if labels.len() > 253 {
+        return Err(format!("Name is {} octets long; DNS allows at most 253", labels.len()));
+    }
+    for label in labels.split('.') {
+        if label.is_empty() {
+            return Err("Name contains an empty label".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("Label {:?} is {} octets long; DNS allows at most 63", label, label.len()));
+        }
+        validate_label(label)?;
+    }
+
+    Ok(NormalizedName { ascii, idna_applied })
+}
+
+/// Letters, digits and inner hyphens (RFC 952/1123), plus `_`, which
+/// synthesized names such as nss-myhostname's `_gateway` use. `xn--` labels
+/// must be valid punycode that IDNA maps back to the same label.
+fn validate_label(label: &str) -> Result<(), String> {
+    if let Some(c) = label.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_') {
+        return Err(format!("Label {:?} contains {:?}; host names allow letters, digits, hyphens and underscores", label, c));
+    }
+    if label.starts_with('-') || label.ends_with('-') {
+        return Err(format!("Label {:?} starts or ends with a hyphen", label));
+    }
+    if label.len() >= 4 && label[..4].eq_ignore_ascii_case("xn--") {
+        let (unicode, decoded) = idna::domain_to_unicode(label);
+        let valid = decoded.is_ok() && idna::domain_to_ascii(&unicode).is_ok_and(|ascii| ascii.eq_ignore_ascii_case(label));
+        if !valid {
+            return Err(format!("Label {:?} is not valid punycode (IDNA A-label)", label));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_names_pass_through() {
+        let name = normalize_host_name("Web-01.example.com.").unwrap();
+        assert_eq!(name.ascii, "Web-01.example.com.");
+        assert!(!name.idna_applied);
+        assert!(normalize_host_name("_gateway").is_ok());
+    }
+
+    #[test]
+    fn unicode_names_become_punycode() {
+        let name = normalize_host_name("bücher.example").unwrap();
+        assert_eq!(name.ascii, "xn--bcher-kva.example");
+        assert!(name.idna_applied);
+        assert!(normalize_host_name("xn--bcher-kva.example").is_ok());
+    }
+
+    #[test]
+    fn invalid_labels_are_rejected() {
+        for name in ["", ".", "a..b", "bad host", "semi;colon.example", "-lead.example", "trail-.example", "xn--.example", "xn--a-ecp.example", "xn--zz-!.example"] {
+            assert!(normalize_host_name(name).is_err(), "{:?}", name);
+        }
+        assert!(normalize_host_name(&"a".repeat(64)).is_err());
+        assert!(normalize_host_name(&vec!["a".repeat(63); 4].join(".")).is_err());
+    }
+
+    #[test]
+    fn special_use_zones() {
+        assert_eq!(special_use_domain("printer.LOCAL."), Some(SpecialUseDomain::Local));
+        assert_eq!(special_use_domain("router.home.arpa"), Some(SpecialUseDomain::HomeArpa));
+        assert_eq!(special_use_domain("notlocal"), None);
+    }
+}
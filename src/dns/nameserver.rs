@@ -0,0 +1,191 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+use super::wire;
+
+/// What one nameserver answered for a name.
+#[derive(Debug, Clone)]
+pub struct NameserverAnswer {
+    /// The response code of the A query, e.g. 3 for NXDOMAIN.
+    pub rcode: u8,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// The name of a response code, as dig prints it.
+pub fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        rcode => format!("RCODE{}", rcode),
+    }
+}
+
+/// The address of a resolv.conf `nameserver` entry on port 53. Link-local
+/// entries with a `%zone` are skipped, as the zone cannot be given to a socket
+/// address here.
+pub fn nameserver_address(nameserver: &str) -> Option<SocketAddr> {
+    nameserver.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53))
+}
+
+/// The names whose address records answer a query for `name`: the name
+/// itself and every CNAME target chained from it.
+fn answer_owners(name: &str, cnames: &[wire::CnameRecord]) -> Vec<String> {
+    let mut owners = vec![name.trim_end_matches('.').to_string()];
+    // Each pass follows one more link; a chain cannot be longer than the list
+    for _ in 0..cnames.len() {
+        let Some(cname) = cnames.iter().find(|cname| {
+            owners.last().is_some_and(|owner| owner.eq_ignore_ascii_case(&cname.name))
+                && !owners.iter().any(|owner| owner.eq_ignore_ascii_case(&cname.target))
+        }) else {
+            break;
+        };
+        owners.push(cname.target.clone());
+    }
+    owners
+}
+
+/// Asks `server` for the A and AAAA records of `name` with recursion
+/// desired, one question per query as res_query sends them, and waits until
+/// `timeout` for each reply.
+pub async fn query_nameserver(server: SocketAddr, name: &str, timeout: Duration) -> anyhow::Result<NameserverAnswer> {
+    let bind: SocketAddr = if server.is_ipv4() { "0.0.0.0:0".parse()? } else { "[::]:0".parse()? };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(server).await?;
+
+    let mut answer = NameserverAnswer {
+        rcode: 0,
+        addresses: Vec::new(),
+    };
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or_default() as u16;
+    for (position, qtype) in [wire::TYPE_A, wire::TYPE_AAAA].into_iter().enumerate() {
+        let id = seed.wrapping_add(position as u16);
+        let question = wire::Question {
+            name: name.trim_end_matches('.').to_string(),
+            qtype,
+            unicast_response: false,
+        };
+        socket.send(&wire::build_query(id, true, &[question]).map_err(|e| anyhow::anyhow!(e))?).await?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = vec![0u8; 4096];
+        let response = loop {
+            let len = timeout_at(deadline, socket.recv(&mut buf))
+                .await
+                .map_err(|_| anyhow::anyhow!("no reply within {}s", timeout.as_secs()))??;
+            match wire::parse_response(&buf[..len]) {
+                Some(response) if response.is_response && response.id == id => break response,
+                _ => continue,
+            }
+        };
+        if qtype == wire::TYPE_A {
+            answer.rcode = response.rcode;
+        }
+        let owners = answer_owners(name, &response.cnames);
+        for record in response.addresses {
+            if !record.in_answer || !owners.iter().any(|owner| owner.eq_ignore_ascii_case(&record.name)) {
+                continue;
+            }
+            if !answer.addresses.contains(&record.addr) {
+                answer.addresses.push(record.addr);
+            }
+        }
+    }
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(reply: &mut Vec<u8>, owner: &[u8], rtype: u16, rdata: &[u8]) {
+        reply.extend_from_slice(owner);
+        reply.extend_from_slice(&rtype.to_be_bytes());
+        reply.extend_from_slice(&wire::CLASS_IN.to_be_bytes());
+        reply.extend_from_slice(&300u32.to_be_bytes());
+        reply.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        reply.extend_from_slice(rdata);
+    }
+
+    /// A loopback nameserver that knows `db.corp` at 10.0.0.5 (A only, with
+    /// unrelated glue in the additional section) and `www.corp` as a CNAME
+    /// for `web.corp` at 10.0.0.7 (next to an unrelated answer), and answers
+    /// NXDOMAIN for every other name. Replies to a stale id first, which
+    /// must be ignored.
+    async fn nameserver() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let query = &buf[..len];
+                let qtype = u16::from_be_bytes([query[len - 4], query[len - 3]]);
+                let mut reply = query.to_vec();
+                reply[2] = 0x81;
+                reply[3] = 0x80;
+                match &query[12..len - 4] {
+                    b"\x02db\x04corp\x00" if qtype == wire::TYPE_A => {
+                        record(&mut reply, &[0xc0, 12], wire::TYPE_A, &[10, 0, 0, 5]);
+                        record(&mut reply, b"\x04evil\x04corp\x00", wire::TYPE_A, &[10, 6, 6, 6]);
+                        (reply[7], reply[11]) = (1, 1);
+                    }
+                    b"\x02db\x04corp\x00" => {}
+                    b"\x03www\x04corp\x00" => {
+                        record(&mut reply, &[0xc0, 12], wire::TYPE_CNAME, b"\x03web\xc0\x10");
+                        reply[7] = 1;
+                        if qtype == wire::TYPE_A {
+                            record(&mut reply, b"\x03web\x04corp\x00", wire::TYPE_A, &[10, 0, 0, 7]);
+                            record(&mut reply, b"\x05other\x04corp\x00", wire::TYPE_A, &[10, 6, 6, 7]);
+                            reply[7] = 3;
+                        }
+                    }
+                    _ => reply[3] = 0x83,
+                }
+                let mut stale = reply.clone();
+                stale[1] = stale[1].wrapping_add(100);
+                socket.send_to(&stale, peer).await.unwrap();
+                socket.send_to(&reply, peer).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn answers_come_from_the_server() {
+        let server = nameserver().await;
+        let answer = query_nameserver(server, "db.corp.", Duration::from_secs(2)).await.unwrap();
+        assert_eq!(rcode_name(answer.rcode), "NOERROR");
+        assert_eq!(answer.addresses, ["10.0.0.5".parse::<IpAddr>().unwrap()]);
+
+        let answer = query_nameserver(server, "db", Duration::from_secs(2)).await.unwrap();
+        assert_eq!(rcode_name(answer.rcode), "NXDOMAIN");
+        assert!(answer.addresses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_records_for_the_name_or_its_cnames_count() {
+        let server = nameserver().await;
+        let answer = query_nameserver(server, "www.corp", Duration::from_secs(2)).await.unwrap();
+        assert_eq!(answer.addresses, ["10.0.0.7".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn silent_servers_time_out() {
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let error = query_nameserver(silent.local_addr().unwrap(), "db.corp", Duration::from_secs(1)).await.unwrap_err();
+        assert_eq!(error.to_string(), "no reply within 1s");
+    }
+
+    #[test]
+    fn nameserver_addresses() {
+        assert_eq!(nameserver_address("10.0.0.53"), Some("10.0.0.53:53".parse().unwrap()));
+        assert_eq!(nameserver_address("fd00::53"), Some("[fd00::53]:53".parse().unwrap()));
+        assert_eq!(nameserver_address("fe80::1%eth0"), None);
+    }
+}
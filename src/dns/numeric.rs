@@ -0,0 +1,223 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone)]
+pub struct NumericHost {
+    pub addr: IpAddr,
+    pub scope_id: Option<String>,
+    pub form: String,
+    pub notes: Vec<String>,
+}
+
+struct AtonPart {
+    value: u64,
+    base: u32,
+}
+
+// Mirrors glibc's inet_aton: up to four parts, each decimal, octal (leading
+// 0) or hex (leading 0x); the last part fills all remaining bytes.
+fn inet_aton_parts(input: &str) -> Option<(Vec<AtonPart>, &str)> {
+    let bytes = input.as_bytes();
+    let mut pos = 0;
+    let mut parts = Vec::new();
+
+    loop {
+        // Every part has to start with a digit, so "" and "." are rejected
+        if pos >= bytes.len() || !bytes[pos].is_ascii_digit() {
+            return None;
+        }
+
+        let mut base = 10;
+        if bytes[pos] == b'0' {
+            pos += 1;
+            base = 8;
+            if pos < bytes.len() && (bytes[pos] == b'x' || bytes[pos] == b'X') {
+                pos += 1;
+                base = 16;
+            }
+        }
+
+        let digits_start = pos;
+        let mut value: u64 = 0;
+        while pos < bytes.len() {
+            let c = bytes[pos];
+            let digit = match c {
+                b'0'..=b'9' if base != 8 || c < b'8' => (c - b'0') as u64,
+                b'8' | b'9' => return None,
+                b'a'..=b'f' | b'A'..=b'F' if base == 16 => (c.to_ascii_lowercase() - b'a' + 10) as u64,
+                _ => break,
+            };
+            value = value * base as u64 + digit;
+            if value > 0xffff_ffff {
+                return None;
+            }
+            pos += 1;
+        }
+        // glibc needs a digit after "0x": "0x" and "0x.1" are not addresses
+        if base == 16 && pos == digits_start {
+            return None;
+        }
+
+        parts.push(AtonPart { value, base });
+
+        if pos < bytes.len() && bytes[pos] == b'.' {
+            if parts.len() == 4 {
+                return None;
+            }
+            pos += 1;
+            continue;
+        }
+        break;
+    }
+
+    Some((parts, &input[pos..]))
+}
+
+fn aton_value(parts: &[AtonPart]) -> Option<u32> {
+    let (last, leading) = parts.split_last()?;
+    if leading.iter().any(|p| p.value > 0xff) {
+        return None;
+    }
+    let last_bits = 32 - 8 * leading.len() as u32;
+    if last_bits < 32 && last.value >> last_bits != 0 {
+        return None;
+    }
+
+    let mut value: u32 = 0;
+    for (i, part) in leading.iter().enumerate() {
+        value |= (part.value as u32) << (24 - 8 * i as u32);
+    }
+    Some(value | last.value as u32)
+}
+
+fn describe_aton_form(parts: &[AtonPart]) -> String {
+    let shape = match parts.len() {
+        1 => "single 32-bit number",
+        2 => "two-part a.b form (b fills 24 bits)",
+        3 => "three-part a.b.c form (c fills 16 bits)",
+        _ => "dotted-quad",
+    };
+    let mut bases: Vec<&str> = Vec::new();
+    if parts.iter().any(|p| p.base == 8 && p.value != 0) {
+        bases.push("octal");
+    }
+    if parts.iter().any(|p| p.base == 16) {
+        bases.push("hexadecimal");
+    }
+    if bases.is_empty() {
+        shape.to_string()
+    } else {
+        format!("{} with {} components", shape, bases.join(" and "))
+    }
+}
+
+fn parse_inet_aton(name: &str) -> Option<NumericHost> {
+    let (parts, rest) = inet_aton_parts(name)?;
+    let addr = Ipv4Addr::from(aton_value(&parts)?);
+    let mut notes = Vec::new();
+
+    if !rest.is_empty() {
+        // Legacy inet_aton stops at whitespace and ignores the rest;
+        // getaddrinfo switched to the exact variant in glibc 2.32.
+        if !rest.starts_with(|c: char| c.is_ascii_whitespace()) {
+            return None;
+        }
+        notes.push(format!(
+            "Trailing text {:?} is ignored by inet_aton/gethostbyname but rejected by getaddrinfo since glibc 2.32",
+            rest
+        ));
+    }
+
+    if name != addr.to_string() {
+        notes.push(format!("inet_pton would reject {:?}; only inet_aton accepts this form", name));
+    }
+
+    Some(NumericHost {
+        addr: IpAddr::V4(addr),
+        scope_id: None,
+        form: describe_aton_form(&parts),
+        notes,
+    })
+}
+
+fn parse_inet_pton6(name: &str) -> Option<NumericHost> {
+    let (addr_part, scope_id) = match name.split_once('%') {
+        Some((addr, scope)) if !scope.is_empty() => (addr, Some(scope.to_string())),
+        Some(_) => return None,
+        None => (name, None),
+    };
+    let addr: Ipv6Addr = addr_part.parse().ok()?;
+
+    let mut notes = Vec::new();
+    if let Some(v4) = addr.to_ipv4_mapped() {
+        notes.push(format!("IPv4-mapped address for {}", v4));
+    }
+    if let Some(scope) = &scope_id {
+        notes.push(format!("Scope id {:?} is resolved to an interface index by getaddrinfo", scope));
+    }
+
+    Some(NumericHost {
+        addr: IpAddr::V6(addr),
+        scope_id,
+        form: "IPv6 text form".to_string(),
+        notes,
+    })
+}
+
+/// Recognizes every host string that getaddrinfo treats as a numeric address
+/// (inet_aton for IPv4, inet_pton for IPv6) before any NSS source is consulted.
+pub fn parse_numeric_host(name: &str) -> Option<NumericHost> {
+    parse_inet_aton(name).or_else(|| parse_inet_pton6(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v4(name: &str) -> Option<String> {
+        parse_numeric_host(name).map(|host| host.addr.to_string())
+    }
+
+    #[test]
+    fn inet_aton_forms() {
+        assert_eq!(v4("127.0.0.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(v4("127.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(v4("10.1.2").as_deref(), Some("10.1.0.2"));
+        assert_eq!(v4("2130706433").as_deref(), Some("127.0.0.1"));
+        assert_eq!(v4("0x7f.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(v4("0177.0.0.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(v4("0").as_deref(), Some("0.0.0.0"));
+    }
+
+    #[test]
+    fn inet_aton_rejects() {
+        for name in ["", ".", "1.", "256.1.1.1", "1.2.3.4.5", "08", "1.2.3.256", "4294967296", "0x", "0x.1", "1.0x", "a.b.c.d"] {
+            assert_eq!(v4(name), None, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn trailing_whitespace_is_noted() {
+        let host = parse_numeric_host("1.2.3.4 junk").unwrap();
+        assert_eq!(host.addr.to_string(), "1.2.3.4");
+        assert!(host.notes.iter().any(|note| note.contains("glibc 2.32")));
+        assert!(parse_numeric_host("1.2.3.4junk").is_none());
+    }
+
+    #[test]
+    fn non_canonical_forms_are_noted() {
+        let host = parse_numeric_host("0x7f.1").unwrap();
+        assert_eq!(host.form, "two-part a.b form (b fills 24 bits) with hexadecimal components");
+        assert!(host.notes.iter().any(|note| note.contains("inet_pton would reject")));
+        assert!(parse_numeric_host("127.0.0.1").unwrap().notes.is_empty());
+    }
+
+    #[test]
+    fn ipv6_with_scope() {
+        let host = parse_numeric_host("fe80::1%eth0").unwrap();
+        assert_eq!(host.addr.to_string(), "fe80::1");
+        assert_eq!(host.scope_id.as_deref(), Some("eth0"));
+        assert!(parse_numeric_host("fe80::1%").is_none());
+        let mapped = parse_numeric_host("::ffff:10.0.0.1").unwrap();
+        assert!(mapped.notes.iter().any(|note| note.contains("IPv4-mapped address for 10.0.0.1")));
+    }
+}
@@ -8,16 +8,20 @@ pub struct ResolvConf {
     pub nameservers: Vec<String>,
     pub search_domains: Vec<String>,
     pub domain: Option<String>,
+    /// Whether a `domain` line came after the last `search` line.
+    pub domain_last: bool,
     pub options: Vec<String>,
 }
 
-#[allow(dead_code)]
-pub fn parse_resolv_conf<P: AsRef<Path>>(path: P) -> io::Result<ResolvConf> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
+pub fn parse_resolv_conf_file<P: AsRef<Path>>(path: P) -> io::Result<ResolvConf> {
+    parse_resolv_conf(io::BufReader::new(fs::File::open(path)?))
+}
+
+pub fn parse_resolv_conf<R: BufRead>(reader: R) -> io::Result<ResolvConf> {
     let mut nameservers = Vec::new();
     let mut search_domains = Vec::new();
     let mut domain = None;
+    let mut domain_last = false;
     let mut options = Vec::new();
 
     for line in reader.lines() {
@@ -38,11 +42,14 @@ pub fn parse_resolv_conf<P: AsRef<Path>>(path: P) -> io::Result<ResolvConf> {
             "nameserver" if parts.len() > 1 => {
                 nameservers.push(parts[1].to_string());
             }
+            // Each `search` or `domain` line replaces the search list, as in glibc
             "search" => {
-                search_domains.extend(parts[1..].iter().map(|s| s.to_string()));
+                search_domains = parts[1..].iter().map(|s| s.to_string()).collect();
+                domain_last = false;
             }
             "domain" if parts.len() > 1 => {
                 domain = Some(parts[1].to_string());
+                domain_last = true;
             }
             "options" => {
                 options.extend(parts[1..].iter().map(|s| s.to_string()));
@@ -55,6 +62,95 @@ pub fn parse_resolv_conf<P: AsRef<Path>>(path: P) -> io::Result<ResolvConf> {
         nameservers,
         search_domains,
         domain,
+        domain_last,
         options,
     })
-}
\ No newline at end of file
+}
+
+impl ResolvConf {
+    /// The `ndots:` option, defaulting to 1 as in glibc (capped at 15).
+    pub fn ndots(&self) -> usize {
+        self.options
+            .iter()
+            .rev()
+            .filter_map(|option| option.strip_prefix("ndots:"))
+            .find_map(|value| value.parse::<usize>().ok())
+            .unwrap_or(1)
+            .min(15)
+    }
+
+    /// The `timeout:` option in seconds, defaulting to 5 as in glibc (capped at 30).
+    pub fn timeout(&self) -> u64 {
+        self.options
+            .iter()
+            .rev()
+            .filter_map(|option| option.strip_prefix("timeout:"))
+            .find_map(|value| value.parse::<u64>().ok())
+            .unwrap_or(5)
+            .min(30)
+    }
+
+    /// The effective search list: the last of `search`/`domain` wins, as in glibc.
+    pub fn search_list(&self) -> Vec<String> {
+        if self.domain_last {
+            self.domain.iter().cloned().collect()
+        } else {
+            self.search_domains.clone()
+        }
+    }
+
+    /// The names res_search tries for `name`, in order. Names with at least
+    /// `ndots` dots are tried as-is first; shorter ones walk the search list first.
+    pub fn search_candidates(&self, name: &str) -> Vec<String> {
+        if let Some(absolute) = name.strip_suffix('.') {
+            return vec![absolute.to_string()];
+        }
+        let suffixed: Vec<String> = self
+            .search_list()
+            .iter()
+            .map(|domain| format!("{}.{}", name, domain.trim_end_matches('.')))
+            .collect();
+
+        let mut candidates = Vec::new();
+        if name.matches('.').count() >= self.ndots() {
+            candidates.push(name.to_string());
+            candidates.extend(suffixed);
+        } else {
+            candidates.extend(suffixed);
+            candidates.push(name.to_string());
+        }
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> ResolvConf {
+        parse_resolv_conf(io::Cursor::new(text)).unwrap()
+    }
+
+    #[test]
+    fn last_search_or_domain_line_wins() {
+        assert_eq!(parse("domain corp\nsearch a.example b.example\n").search_list(), vec!["a.example", "b.example"]);
+        assert_eq!(parse("search a.example b.example\ndomain corp\n").search_list(), vec!["corp"]);
+        assert_eq!(parse("search a.example\nsearch b.example\n").search_list(), vec!["b.example"]);
+        assert_eq!(parse("domain corp\n").search_list(), vec!["corp"]);
+        assert!(parse("nameserver 10.0.0.1\n").search_list().is_empty());
+    }
+
+    #[test]
+    fn ndots_and_candidates() {
+        let conf = parse("# comment\nnameserver 10.0.0.1\nsearch svc.cluster.local cluster.local\noptions ndots:5 edns0\n");
+        assert_eq!(conf.nameservers, vec!["10.0.0.1"]);
+        assert_eq!(conf.ndots(), 5);
+        assert_eq!(
+            conf.search_candidates("db"),
+            vec!["db.svc.cluster.local", "db.cluster.local", "db"]
+        );
+        assert_eq!(conf.search_candidates("example.com."), vec!["example.com"]);
+        assert_eq!(parse("options ndots:1\nsearch corp\n").search_candidates("a.b"), vec!["a.b", "a.b.corp"]);
+        assert_eq!(parse("options ndots:40\n").ndots(), 15);
+    }
+}
@@ -1,30 +1,125 @@
 use zbus::{dbus_proxy, Connection};
 use anyhow::Result;
 
+/// An address as resolved sends it: interface index, address family and bytes.
+type BusAddress = (i32, i32, Vec<u8>);
+
 #[dbus_proxy(
-    interface = "org.freedesktop.resolve1",
+    interface = "org.freedesktop.resolve1.Manager",
     default_service = "org.freedesktop.resolve1",
     default_path = "/org/freedesktop/resolve1"
 )]
 trait Resolve1 {
-    async fn resolve_hostname(&self, ifindex: i32, name: &str, family: i32, flags: u64) -> zbus::Result<(Vec<String>, String, u32)>;
+    async fn resolve_hostname(&self, ifindex: i32, name: &str, family: i32, flags: u64) -> zbus::Result<(Vec<BusAddress>, String, u64)>;
+    #[dbus_proxy(property, name = "DNS")]
+    fn dns(&self) -> zbus::Result<Vec<BusAddress>>;
+    #[dbus_proxy(property)]
+    fn domains(&self) -> zbus::Result<Vec<(i32, String, bool)>>;
     // Other methods as needed
 }
 
-pub async fn resolve_hostname_via_resolved(name: &str) -> Result<String> {
-    let connection = Connection::system().await?;
-    let proxy = Resolve1Proxy::new(&connection).await?;
-    let (addresses, _, _) = proxy.resolve_hostname(0, name, 0, 0).await?;
-    Ok(addresses.into_iter().next().unwrap_or_default())
+/// Every address systemd-resolved returns for `name`, in its order.
+pub async fn resolve_addresses_via_resolved(connection: &Connection, name: &str) -> Result<Vec<String>> {
+    let proxy = Resolve1Proxy::new(connection).await?;
+    let (addresses, _canonical, _flags) = proxy.resolve_hostname(0, name, 0, 0).await?;
+    Ok(addresses.iter().map(|(_ifindex, _family, address)| format_address(address)).collect())
+}
+
+/// An address in the byte form resolved uses on the bus.
+fn format_address(address: &[u8]) -> String {
+    match address.len() {
+        4 => <[u8; 4]>::try_from(address).map(|a| std::net::IpAddr::from(a).to_string()).unwrap_or_default(),
+        16 => <[u8; 16]>::try_from(address).map(|a| std::net::IpAddr::from(a).to_string()).unwrap_or_default(),
+        _ => format!("{:?}", address),
+    }
+}
+
+fn link_label(ifindex: i32) -> String {
+    if ifindex == 0 {
+        "global".to_string()
+    } else {
+        format!("link {}", ifindex)
+    }
 }
 
-pub async fn resolve_hostname_libc(name: &str) -> anyhow::Result<String> {
+/// The DNS servers and search/routing domains systemd-resolved holds per
+/// link, one line each, e.g. `link 2 dns 10.0.0.1` or `link 2 domain ~corp`.
+pub async fn resolved_link_state(connection: &Connection) -> Result<Vec<String>> {
+    let proxy = Resolve1Proxy::new(connection).await?;
+    let mut lines = Vec::new();
+    for (ifindex, _family, address) in proxy.dns().await? {
+        lines.push(format!("{} dns {}", link_label(ifindex), format_address(&address)));
+    }
+    for (ifindex, domain, routing_only) in proxy.domains().await? {
+        lines.push(format!("{} domain {}{}", link_label(ifindex), if routing_only { "~" } else { "" }, domain));
+    }
+    Ok(lines)
+}
+
+/// Every address getaddrinfo returns for `name`, of either family.
+pub async fn resolve_addresses_libc(name: &str) -> anyhow::Result<Vec<std::net::IpAddr>> {
     use tokio::net::lookup_host;
-    let addrs = lookup_host((name, 0)).await?;
-    for addr in addrs {
-        if let std::net::IpAddr::V4(ip) = addr.ip() {
-            return Ok(ip.to_string());
+    let mut addresses: Vec<std::net::IpAddr> = Vec::new();
+    for addr in lookup_host((name, 0)).await? {
+        if !addresses.contains(&addr.ip()) {
+            addresses.push(addr.ip());
+        }
+    }
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use zbus::{dbus_interface, ConnectionBuilder, Guid};
+
+    use super::*;
+
+    /// Stands in for systemd-resolved, which knows `db.corp` on link 2.
+    struct Manager;
+
+    #[dbus_interface(name = "org.freedesktop.resolve1.Manager")]
+    impl Manager {
+        fn resolve_hostname(&self, _ifindex: i32, name: &str, _family: i32, _flags: u64) -> zbus::fdo::Result<(Vec<BusAddress>, String, u64)> {
+            match name {
+                // AF_INET and AF_INET6
+                "db.corp" | "db.corp." => Ok((vec![(2, 2, vec![10, 0, 0, 5]), (2, 10, vec![0xfd; 16])], "db.corp".to_string(), 1)),
+                _ => Err(zbus::fdo::Error::Failed(format!("'{}' not found", name))),
+            }
+        }
+
+        #[dbus_interface(property, name = "DNS")]
+        fn dns(&self) -> Vec<BusAddress> {
+            vec![(2, 2, vec![10, 0, 0, 53])]
+        }
+
+        #[dbus_interface(property)]
+        fn domains(&self) -> Vec<(i32, String, bool)> {
+            vec![(2, "corp".to_string(), true)]
         }
     }
-    Err(anyhow::anyhow!("No IPv4 address found"))
-}
\ No newline at end of file
+
+    /// A peer-to-peer connection to the stand-in, with its server end.
+    async fn connect() -> (Connection, Connection) {
+        let (server, client) = UnixStream::pair().unwrap();
+        let guid = Guid::generate();
+        let server = ConnectionBuilder::unix_stream(server).server(&guid).p2p().serve_at("/org/freedesktop/resolve1", Manager).unwrap().build();
+        let client = ConnectionBuilder::unix_stream(client).p2p().build();
+        tokio::try_join!(client, server).unwrap()
+    }
+
+    #[tokio::test]
+    async fn resolve_hostname_decodes_addresses() {
+        let (connection, _server) = connect().await;
+        let addresses = resolve_addresses_via_resolved(&connection, "db.corp.").await.unwrap();
+        assert_eq!(addresses, ["10.0.0.5", "fdfd:fdfd:fdfd:fdfd:fdfd:fdfd:fdfd:fdfd"]);
+        assert!(resolve_addresses_via_resolved(&connection, "db").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn link_state_lists_servers_and_domains() {
+        let (connection, _server) = connect().await;
+        assert_eq!(resolved_link_state(&connection).await.unwrap(), ["link 2 dns 10.0.0.53", "link 2 domain ~corp"]);
+    }
+}
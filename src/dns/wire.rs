@@ -0,0 +1,171 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_AAAA: u16 = 28;
+pub const CLASS_IN: u16 = 1;
+
+/// Top bit of the class field: "unicast response" in mDNS questions and
+/// "cache flush" in mDNS answers.
+const CLASS_TOP_BIT: u16 = 0x8000;
+
+#[derive(Debug, Clone)]
+pub struct Question {
+    pub name: String,
+    pub qtype: u16,
+    pub unicast_response: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AddressRecord {
+    pub name: String,
+    pub addr: IpAddr,
+    #[allow(dead_code)]
+    pub ttl: u32,
+    /// Whether the record came from the answer section rather than the
+    /// authority or additional sections.
+    pub in_answer: bool,
+}
+
+/// A CNAME record from the answer section.
+#[derive(Debug, Clone)]
+pub struct CnameRecord {
+    pub name: String,
+    pub target: String,
+}
+
+fn encode_name(out: &mut Vec<u8>, name: &str) -> Result<(), String> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("Cannot encode label {:?}", label));
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+    Ok(())
+}
+
+pub fn build_query(id: u16, recursion_desired: bool, questions: &[Question]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(512);
+    out.extend_from_slice(&id.to_be_bytes());
+    let flags: u16 = if recursion_desired { 0x0100 } else { 0 };
+    out.extend_from_slice(&flags.to_be_bytes());
+    out.extend_from_slice(&(questions.len() as u16).to_be_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+    for question in questions {
+        encode_name(&mut out, &question.name)?;
+        out.extend_from_slice(&question.qtype.to_be_bytes());
+        let class = if question.unicast_response { CLASS_IN | CLASS_TOP_BIT } else { CLASS_IN };
+        out.extend_from_slice(&class.to_be_bytes());
+    }
+
+    Ok(out)
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*buf.get(pos)?, *buf.get(pos + 1)?]))
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes([*buf.get(pos)?, *buf.get(pos + 1)?, *buf.get(pos + 2)?, *buf.get(pos + 3)?]))
+}
+
+/// Reads a possibly compressed name and returns it with the offset just
+/// past its encoding in the original position.
+fn read_name(buf: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *buf.get(pos)? as usize;
+        if len & 0xc0 == 0xc0 {
+            let pointer = (read_u16(buf, pos)? & 0x3fff) as usize;
+            end.get_or_insert(pos + 2);
+            jumps += 1;
+            if jumps > 64 {
+                return None;
+            }
+            pos = pointer;
+            continue;
+        }
+        if len == 0 {
+            end.get_or_insert(pos + 1);
+            break;
+        }
+        let label = buf.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        pos += 1 + len;
+    }
+
+    Some((labels.join("."), end?))
+}
+
+pub struct Response {
+    pub id: u16,
+    pub is_response: bool,
+    pub rcode: u8,
+    pub addresses: Vec<AddressRecord>,
+    pub cnames: Vec<CnameRecord>,
+}
+
+/// Parses the header, the A/AAAA records from every section and the CNAME
+/// records from the answer section; other record types are skipped.
+pub fn parse_response(buf: &[u8]) -> Option<Response> {
+    let id = read_u16(buf, 0)?;
+    let flags = read_u16(buf, 2)?;
+    let qdcount = read_u16(buf, 4)?;
+    let ancount = read_u16(buf, 6)?;
+    let nscount = read_u16(buf, 8)?;
+    let arcount = read_u16(buf, 10)?;
+    let mut pos = 12;
+
+    for _ in 0..qdcount {
+        let (_, next) = read_name(buf, pos)?;
+        pos = next + 4;
+    }
+
+    let mut addresses = Vec::new();
+    let mut cnames = Vec::new();
+    for index in 0..(ancount as u32 + nscount as u32 + arcount as u32) {
+        let in_answer = index < ancount as u32;
+        let (name, next) = read_name(buf, pos)?;
+        let rtype = read_u16(buf, next)?;
+        let class = read_u16(buf, next + 2)? & !CLASS_TOP_BIT;
+        let ttl = read_u32(buf, next + 4)?;
+        let rdlength = read_u16(buf, next + 8)? as usize;
+        let rdata = buf.get(next + 10..next + 10 + rdlength)?;
+        pos = next + 10 + rdlength;
+
+        if class != CLASS_IN {
+            continue;
+        }
+        if rtype == TYPE_CNAME && in_answer {
+            // The target may be compressed against earlier parts of the message
+            if let Some((target, _)) = read_name(buf, next + 10) {
+                cnames.push(CnameRecord { name, target });
+            }
+            continue;
+        }
+        let addr = match (rtype, rdata.len()) {
+            (TYPE_A, 4) => IpAddr::V4(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3])),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(rdata);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => continue,
+        };
+        addresses.push(AddressRecord { name, addr, ttl, in_answer });
+    }
+
+    Some(Response {
+        id,
+        is_response: flags & 0x8000 != 0,
+        rcode: (flags & 0x000f) as u8,
+        addresses,
+        cnames,
+    })
+}
@@ -1,9 +1,31 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::nss::nsswitch::{NssAction, NssSource, NssStatus};
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Outcome {
     Match(String),
     NoMatch,
     Error(String),
     Unsupported(String),
+    /// A temporary failure, such as a busy server: NSS status TRYAGAIN.
+    TryAgain(String),
+    Info(String),
+}
+
+impl Outcome {
+    /// "Match: 10.0.0.5", "No match", and so on, as `--why` prints it.
+    pub fn describe(&self) -> String {
+        match self {
+            Outcome::Match(value) => format!("Match: {}", value),
+            Outcome::NoMatch => "No match".to_string(),
+            Outcome::Error(e) => format!("Error: {}", e),
+            Outcome::Unsupported(r) => format!("Unsupported: {}", r),
+            Outcome::TryAgain(e) => format!("Try again: {}", e),
+            Outcome::Info(i) => format!("Info: {}", i),
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -13,11 +35,298 @@ pub struct DecisionStep {
     pub reason: String,
 }
 
-#[allow(dead_code)]
-pub type DecisionTree = Vec<DecisionStep>;
+/// One node of a resolution tree: a context note, an nsswitch source
+/// invocation, or a sub-step of one such as a search candidate.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DecisionNode {
+    pub source: String,
+    pub outcome: Outcome,
+    pub reason: String,
+    /// The NSS status the source reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<NssStatus>,
+    /// What the walk did with that status: return, continue or merge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<NssAction>,
+    /// The explicit `[STATUS=action]` criterion that chose the action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub criterion: Option<String>,
+    /// False for sources the real lookup never reaches.
+    pub taken: bool,
+    /// The branch not taken: for a taken source, the statuses that would
+    /// have led elsewhere; for a source not taken, what would reach it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// How long the source took, for nodes that stand for a whole source.
+    #[serde(skip)]
+    pub elapsed: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DecisionNode>,
+}
+
+impl DecisionNode {
+    pub fn from_step(step: &DecisionStep, taken: bool) -> Self {
+        DecisionNode {
+            source: step.source.clone(),
+            outcome: step.outcome.clone(),
+            reason: step.reason.clone(),
+            status: None,
+            action: None,
+            criterion: None,
+            taken,
+            condition: None,
+            elapsed: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DecisionTree {
+    pub nodes: Vec<DecisionNode>,
+}
 
-#[allow(dead_code)]
-pub fn build_decision_tree(/* params */) -> DecisionTree {
-    // TODO: Implement decision tree building
-    vec![]
-}
\ No newline at end of file
+/// The steps one nsswitch source produced, as a range of the lookup's steps.
+#[derive(Debug, Clone)]
+pub struct SourceSpan {
+    /// Position of the source in the nsswitch line.
+    pub index: usize,
+    pub steps: Range<usize>,
+    pub elapsed: Duration,
+}
+
+/// What an nsswitch walk consulted, recorded while it runs.
+#[derive(Debug, Clone, Default)]
+pub struct Walk {
+    pub database: String,
+    pub sources: Vec<NssSource>,
+    pub spans: Vec<SourceSpan>,
+    /// Position of the source whose status ended the walk.
+    pub decided: Option<usize>,
+}
+
+impl Walk {
+    pub fn new(database: &str, sources: &[NssSource]) -> Self {
+        Walk {
+            database: database.to_string(),
+            sources: sources.to_vec(),
+            spans: Vec::new(),
+            decided: None,
+        }
+    }
+}
+
+const STATUSES: [NssStatus; 4] = [NssStatus::Success, NssStatus::NotFound, NssStatus::Unavail, NssStatus::TryAgain];
+
+/// The statuses on which `source` hands the walk on to the next source.
+fn onward_statuses(source: &NssSource) -> Vec<&'static str> {
+    STATUSES
+        .iter()
+        .filter(|status| source.action_for(**status).0 != NssAction::Return)
+        .map(|status| status.as_str())
+        .collect()
+}
+
+/// The statuses on which `source` ends the walk.
+fn returning_statuses(source: &NssSource) -> Vec<&'static str> {
+    STATUSES
+        .iter()
+        .filter(|status| source.action_for(**status).0 == NssAction::Return)
+        .map(|status| status.as_str())
+        .collect()
+}
+
+/// "A, B or C".
+fn either(statuses: &[&str]) -> String {
+    match statuses {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [rest @ .., last] => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+/// The status a source reported: that of its last step with one.
+fn source_status(steps: &[DecisionStep]) -> Option<(NssStatus, &DecisionStep)> {
+    steps
+        .iter()
+        .rev()
+        .find_map(|step| NssStatus::from_outcome(&step.outcome).map(|status| (status, step)))
+}
+
+fn source_node(walk: &Walk, span: &SourceSpan, steps: &[DecisionStep], taken: bool) -> DecisionNode {
+    let source = &walk.sources[span.index];
+    let own = &steps[span.steps.clone()];
+    let decisive = source_status(own);
+    let mut node = match own {
+        [single] => DecisionNode::from_step(single, taken),
+        // Several steps, or none from a source that recorded nothing
+        _ => DecisionNode {
+            source: source.name.clone(),
+            outcome: decisive.map(|(_, step)| step.outcome.clone()).unwrap_or(Outcome::NoMatch),
+            reason: decisive.map(|(_, step)| step.reason.clone()).unwrap_or_default(),
+            status: None,
+            action: None,
+            criterion: None,
+            taken,
+            condition: None,
+            elapsed: None,
+            children: own.iter().map(|step| DecisionNode::from_step(step, taken)).collect(),
+        },
+    };
+    node.elapsed = Some(span.elapsed);
+    let Some((status, _)) = decisive else {
+        return node;
+    };
+    node.status = Some(status);
+    if !taken {
+        return node;
+    }
+
+    let (action, criterion) = source.action_for(status);
+    node.action = Some(action);
+    node.criterion = criterion.map(|criterion| criterion.describe());
+    node.condition = if action == NssAction::Return {
+        let onward = onward_statuses(source);
+        match walk.sources.get(span.index + 1) {
+            Some(next) if !onward.is_empty() => Some(format!("would have continued to {} on {}", next.name, either(&onward))),
+            _ => None,
+        }
+    } else {
+        let returning = returning_statuses(source);
+        (!returning.is_empty()).then(|| format!("would have returned on {}", either(&returning)))
+    };
+    node
+}
+
+/// What it would take for the walk to reach `index` when `decided` ended it.
+fn reach_condition(walk: &Walk, decided: usize, index: usize) -> String {
+    let mut parts = Vec::new();
+    for source in &walk.sources[decided..index] {
+        let onward = onward_statuses(source);
+        if onward.is_empty() {
+            return format!("unreachable: {} returns on every status", source.name);
+        }
+        parts.push(format!("{} returned {}", source.name, either(&onward)));
+    }
+    format!("reached only if {}", parts.join(" and "))
+}
+
+/// Builds the tree of a lookup from its flat steps and the walk that
+/// produced them. Steps outside every span (namespace context, notes after
+/// the walk) become top-level nodes; the walk's own criterion notes are
+/// folded into their source's node. Sources after the decision are not
+/// taken, with the steps they produced if `--all-sources` consulted them.
+pub fn build_decision_tree(steps: &[DecisionStep], walk: &Walk) -> DecisionTree {
+    let criterion_source = format!("nsswitch ({})", walk.database);
+    let walk_end = walk.spans.iter().map(|span| span.steps.end).max().unwrap_or(0);
+    let mut nodes = Vec::new();
+    let mut spans = walk.spans.iter().peekable();
+    let mut position = 0;
+
+    while position < steps.len() || spans.peek().is_some() {
+        if position == walk_end && spans.peek().is_none() {
+            nodes.extend(unreached(walk));
+        }
+        match spans.peek() {
+            Some(span) if span.steps.start <= position || position >= steps.len() => {
+                let taken = walk.decided.is_none_or(|decided| span.index <= decided);
+                let mut node = source_node(walk, span, steps, taken);
+                if let (false, Some(decided)) = (taken, walk.decided) {
+                    node.condition = Some(format!("{}; consulted only for --all-sources", reach_condition(walk, decided, span.index)));
+                }
+                nodes.push(node);
+                position = span.steps.end.max(position);
+                spans.next();
+            }
+            _ => {
+                let step = &steps[position];
+                if step.source != criterion_source {
+                    nodes.push(DecisionNode::from_step(step, true));
+                }
+                position += 1;
+            }
+        }
+    }
+    if walk_end >= steps.len() {
+        nodes.extend(unreached(walk));
+    }
+
+    DecisionTree { nodes }
+}
+
+/// Nodes for the sources after the decision that were never consulted.
+fn unreached(walk: &Walk) -> Vec<DecisionNode> {
+    let Some(decided) = walk.decided else {
+        return Vec::new();
+    };
+    ((decided + 1)..walk.sources.len())
+        .filter(|index| !walk.spans.iter().any(|span| span.index == *index))
+        .map(|index| {
+            let step = DecisionStep {
+                source: walk.sources[index].name.clone(),
+                outcome: Outcome::Info("not consulted".to_string()),
+                reason: String::new(),
+            };
+            DecisionNode {
+                condition: Some(reach_condition(walk, decided, index)),
+                ..DecisionNode::from_step(&step, false)
+            }
+        })
+        .collect()
+}
+
+/// A source consulted after the lookup had decided whose answer differs
+/// from the one returned.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Conflict {
+    pub source: String,
+    pub answer: String,
+    pub winner: Option<String>,
+}
+
+/// Steps from sources the real lookup never reaches, recorded with `--all-sources`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Shadowed {
+    /// Shown in the tree as branches not taken.
+    #[serde(skip)]
+    pub steps: Vec<DecisionStep>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Collects the matches among `steps` that disagree with `winner`. `agrees`
+/// decides whether a shadowed match is consistent with the winner, so hosts
+/// can compare address sets rather than first addresses.
+pub fn shadow(steps: Vec<DecisionStep>, winner: Option<&str>, agrees: impl Fn(&DecisionStep, &str) -> bool) -> Shadowed {
+    let conflicts = steps
+        .iter()
+        .filter_map(|step| match &step.outcome {
+            Outcome::Match(answer) if !winner.is_some_and(|winner| agrees(step, winner)) => Some(Conflict {
+                source: step.source.clone(),
+                answer: answer.clone(),
+                winner: winner.map(String::from),
+            }),
+            _ => None,
+        })
+        .collect();
+    Shadowed { steps, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nss::nsswitch::parse_sources;
+
+    #[test]
+    fn sources_without_steps() {
+        let mut walk = Walk::new("hosts", &parse_sources("silent files"));
+        walk.spans.push(SourceSpan {
+            index: 0,
+            steps: 0..0,
+            elapsed: Duration::ZERO,
+        });
+        let tree = build_decision_tree(&[], &walk);
+        assert_eq!(tree.nodes[0].source, "silent");
+        assert!(tree.nodes[0].children.is_empty());
+        assert_eq!(tree.nodes[0].status, None);
+    }
+}
@@ -0,0 +1,232 @@
+use super::decision_tree::{DecisionNode, DecisionTree};
+
+/// A node of the graph with the id it is drawn under.
+struct Drawn<'a> {
+    id: String,
+    node: &'a DecisionNode,
+}
+
+/// The status→action that fired at a source, e.g. "NOTFOUND → return [NOTFOUND=return]".
+fn fired(node: &DecisionNode) -> Option<String> {
+    let (status, action) = (node.status.as_ref()?, node.action.as_ref()?);
+    Some(match &node.criterion {
+        Some(criterion) => format!("{} → {} {}", status.as_str(), action.as_str(), criterion),
+        None => format!("{} → {}", status.as_str(), action.as_str()),
+    })
+}
+
+/// The status the previous source would have had to return to reach a
+/// source that was not taken: the last clause of its condition.
+fn reach(node: &DecisionNode) -> String {
+    let condition = node.condition.as_deref().unwrap_or("not taken");
+    let condition = condition.split(';').next().unwrap_or(condition);
+    let condition = condition.strip_prefix("reached only if ").unwrap_or(condition);
+    condition.rsplit(" and ").next().unwrap_or(condition).to_string()
+}
+
+/// The resolver or nameserver a sub-step went to: the parenthesized part of
+/// its source, e.g. `nameserver 10.0.0.53` for "dns (nameserver 10.0.0.53)".
+fn server(node: &DecisionNode) -> &str {
+    match node.source.split_once(" (") {
+        Some((_, server)) => server.strip_suffix(')').unwrap_or(server),
+        None => &node.source,
+    }
+}
+
+/// Sub-steps grouped by the server they went to, in first-seen order.
+fn by_server(children: &[DecisionNode]) -> Vec<(&str, Vec<(usize, &DecisionNode)>)> {
+    let mut groups: Vec<(&str, Vec<(usize, &DecisionNode)>)> = Vec::new();
+    for (i, child) in children.iter().enumerate() {
+        match groups.iter_mut().find(|(name, _)| *name == server(child)) {
+            Some((_, members)) => members.push((i, child)),
+            None => groups.push((server(child), vec![(i, child)])),
+        }
+    }
+    groups
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    label: Option<String>,
+    taken: bool,
+}
+
+/// The edges of the resolution path: the taken chain from the query to the
+/// answer, each labeled with the status→action that fired at its tail, and
+/// an edge to each source that was not taken.
+fn edges(drawn: &[Drawn]) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    let mut last_taken: Option<&Drawn> = None;
+    let mut previous = "query".to_string();
+    for item in drawn {
+        if item.node.taken {
+            edges.push(Edge {
+                from: last_taken.map(|last| last.id.clone()).unwrap_or_else(|| "query".to_string()),
+                to: item.id.clone(),
+                label: last_taken.and_then(|last| fired(last.node)),
+                taken: true,
+            });
+            last_taken = Some(item);
+        } else {
+            edges.push(Edge {
+                from: previous.clone(),
+                to: item.id.clone(),
+                label: Some(reach(item.node)),
+                taken: false,
+            });
+        }
+        previous = item.id.clone();
+    }
+    edges.push(Edge {
+        from: last_taken.map(|last| last.id.clone()).unwrap_or_else(|| "query".to_string()),
+        to: "answer".to_string(),
+        label: last_taken.and_then(|last| fired(last.node)),
+        taken: true,
+    });
+    edges
+}
+
+fn drawn(tree: &DecisionTree) -> Vec<Drawn<'_>> {
+    tree.nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| Drawn { id: format!("n{}", i), node })
+        .collect()
+}
+
+fn answer_label(name: &str, result: Option<&str>) -> String {
+    match result {
+        Some(result) => format!("{} → {}", name, result),
+        None => format!("{} not resolved", name),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn dot_node(id: &str, node: &DecisionNode, indent: &str) -> String {
+    let style = if node.taken {
+        "style=\"rounded,filled,bold\", fillcolor=\"#e3f2e1\", color=\"#2e7d32\""
+    } else {
+        "style=\"rounded,dashed\", color=gray50, fontcolor=gray40"
+    };
+    let mut label = format!("{}\n{}", node.source, node.outcome.describe());
+    if let Some(status) = &node.status {
+        label.push_str(&format!("\n{}", status.as_str()));
+    }
+    format!("{}{} [label=\"{}\", {}];\n", indent, id, dot_escape(&label), style)
+}
+
+/// A Graphviz digraph of the resolution of `name`: sources as nodes, the
+/// taken path in bold green, branches not taken dashed, and each source's
+/// sub-steps in a cluster, grouped per server.
+pub fn to_dot(name: &str, result: Option<&str>, tree: &DecisionTree) -> String {
+    let drawn = drawn(tree);
+    let mut dot = String::from("digraph resolution {\n");
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str("  node [shape=box, fontname=\"Helvetica\"];\n");
+    dot.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
+    dot.push_str(&format!("  query [label=\"{}\", shape=oval];\n", dot_escape(name)));
+
+    for item in &drawn {
+        if item.node.children.is_empty() {
+            dot.push_str(&dot_node(&item.id, item.node, "  "));
+            continue;
+        }
+        dot.push_str(&format!("  subgraph cluster_{} {{\n", item.id));
+        dot.push_str(&format!("    label=\"{}\";\n", dot_escape(&item.node.source)));
+        dot.push_str("    style=rounded;\n");
+        dot.push_str(&dot_node(&item.id, item.node, "    "));
+        for (g, (server, members)) in by_server(&item.node.children).into_iter().enumerate() {
+            dot.push_str(&format!("    subgraph cluster_{}_{} {{\n", item.id, g));
+            dot.push_str(&format!("      label=\"{}\";\n", dot_escape(server)));
+            for (i, child) in &members {
+                dot.push_str(&dot_node(&format!("{}_{}", item.id, i), child, "      "));
+            }
+            dot.push_str("    }\n");
+        }
+        // Sub-steps run in order under their source
+        let mut from = item.id.clone();
+        for i in 0..item.node.children.len() {
+            let to = format!("{}_{}", item.id, i);
+            dot.push_str(&format!("    {} -> {} [style=dotted, arrowhead=none];\n", from, to));
+            from = to;
+        }
+        dot.push_str("  }\n");
+    }
+
+    dot.push_str(&format!(
+        "  answer [label=\"{}\", shape=oval, style=\"filled,bold\", fillcolor=\"#e3f2e1\", color=\"#2e7d32\"];\n",
+        dot_escape(&answer_label(name, result))
+    ));
+    for edge in edges(&drawn) {
+        let mut attributes = if edge.taken {
+            vec!["color=\"#2e7d32\"".to_string(), "penwidth=2".to_string()]
+        } else {
+            vec!["style=dashed".to_string(), "color=gray50".to_string(), "fontcolor=gray40".to_string()]
+        };
+        if let Some(label) = &edge.label {
+            attributes.push(format!("label=\"{}\"", dot_escape(label)));
+        }
+        dot.push_str(&format!("  {} -> {} [{}];\n", edge.from, edge.to, attributes.join(", ")));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+fn mermaid_node(id: &str, node: &DecisionNode, indent: &str) -> String {
+    let mut label = format!("{}\n{}", node.source, node.outcome.describe());
+    if let Some(status) = &node.status {
+        label.push_str(&format!("\n{}", status.as_str()));
+    }
+    let class = if node.taken { "taken" } else { "skipped" };
+    format!("{}{}[\"{}\"]:::{}\n", indent, id, mermaid_escape(&label), class)
+}
+
+/// The same graph as [`to_dot`] as a Mermaid flowchart, for wiki pages.
+pub fn to_mermaid(name: &str, result: Option<&str>, tree: &DecisionTree) -> String {
+    let drawn = drawn(tree);
+    let mut mermaid = String::from("flowchart LR\n");
+    mermaid.push_str("  classDef taken fill:#e3f2e1,stroke:#2e7d32,stroke-width:2px\n");
+    mermaid.push_str("  classDef skipped stroke-dasharray:5 5,color:#666\n");
+    mermaid.push_str(&format!("  query([\"{}\"])\n", mermaid_escape(name)));
+
+    for item in &drawn {
+        if item.node.children.is_empty() {
+            mermaid.push_str(&mermaid_node(&item.id, item.node, "  "));
+            continue;
+        }
+        mermaid.push_str(&format!("  subgraph cluster_{}[\"{}\"]\n", item.id, mermaid_escape(&item.node.source)));
+        mermaid.push_str(&mermaid_node(&item.id, item.node, "    "));
+        for (g, (server, members)) in by_server(&item.node.children).into_iter().enumerate() {
+            mermaid.push_str(&format!("    subgraph cluster_{}_{}[\"{}\"]\n", item.id, g, mermaid_escape(server)));
+            for (i, child) in &members {
+                mermaid.push_str(&mermaid_node(&format!("{}_{}", item.id, i), child, "      "));
+            }
+            mermaid.push_str("    end\n");
+        }
+        let mut from = item.id.clone();
+        for i in 0..item.node.children.len() {
+            let to = format!("{}_{}", item.id, i);
+            mermaid.push_str(&format!("    {} -.- {}\n", from, to));
+            from = to;
+        }
+        mermaid.push_str("  end\n");
+    }
+
+    mermaid.push_str(&format!("  answer([\"{}\"]):::taken\n", mermaid_escape(&answer_label(name, result))));
+    for edge in edges(&drawn) {
+        let arrow = if edge.taken { "==>" } else { "-.->" };
+        match &edge.label {
+            Some(label) => mermaid.push_str(&format!("  {} {}|\"{}\"| {}\n", edge.from, arrow, mermaid_escape(label), edge.to)),
+            None => mermaid.push_str(&format!("  {} {} {}\n", edge.from, arrow, edge.to)),
+        }
+    }
+    mermaid
+}
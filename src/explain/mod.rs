@@ -1 +1,3 @@
-pub mod decision_tree;
\ No newline at end of file
+pub mod decision_tree;
+pub mod graph;
+pub mod why_not;
@@ -0,0 +1,202 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use super::decision_tree::{DecisionStep, Outcome};
+
+/// An address a source produced while walking every source, whether or not
+/// it became the answer.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// Position of the source in the nsswitch line.
+    pub index: usize,
+    pub source: String,
+    pub address: String,
+    /// Where in the source it came from, e.g. "line 4 of /etc/hosts".
+    pub origin: String,
+    /// The name actually queried when the source expanded the lookup name
+    /// through the search list.
+    pub query: Option<String>,
+    /// Why the source drops the address instead of returning it.
+    pub filtered: Option<String>,
+}
+
+impl Candidate {
+    /// An address as a source reports it, before the resolver records which
+    /// source that was.
+    pub fn new(address: &str, origin: String) -> Self {
+        Candidate {
+            index: 0,
+            source: String::new(),
+            address: address.to_string(),
+            origin,
+            query: None,
+            filtered: None,
+        }
+    }
+}
+
+/// Where the real nsswitch walk ended.
+#[derive(Debug, Clone)]
+pub struct Stop {
+    pub index: usize,
+    pub source: String,
+    pub status: &'static str,
+    /// The explicit `[STATUS=action]` criterion that ended the walk.
+    pub criterion: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WhyNot {
+    pub expected: String,
+    /// Whether the expected address is the answer after all.
+    pub produced: bool,
+    pub explanation: Vec<String>,
+}
+
+/// Compares addresses by value, so `::ffff:10.0.0.5` style spellings and
+/// scope ids do not hide a match.
+pub fn same_address(a: &str, b: &str) -> bool {
+    let parse = |s: &str| s.split('%').next().unwrap_or_default().parse::<IpAddr>().ok();
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a == b || a.to_canonical() == b.to_canonical(),
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+fn outcome_label(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Match(value) => format!("match {}", value),
+        Outcome::NoMatch => "no match".to_string(),
+        Outcome::Error(e) => format!("error ({})", e),
+        Outcome::Unsupported(why) => format!("unavailable ({})", why),
+        Outcome::TryAgain(why) => format!("temporarily unavailable ({})", why),
+        Outcome::Info(info) => info.clone(),
+    }
+}
+
+fn describe(candidate: &Candidate) -> String {
+    match &candidate.query {
+        Some(query) => format!("{} ({}, queried as {})", candidate.address, candidate.origin, query),
+        None => format!("{} ({})", candidate.address, candidate.origin),
+    }
+}
+
+/// The candidate a source returns first: its first unfiltered address.
+fn winner(candidates: &[Candidate], index: usize) -> Option<&Candidate> {
+    candidates.iter().find(|c| c.index == index && c.filtered.is_none())
+}
+
+fn explain_hit(hit: &Candidate, candidates: &[Candidate], stop: Option<&Stop>) -> String {
+    if let Some(why) = &hit.filtered {
+        return format!("{} found {} but drops it: {}", hit.source, describe(hit), why);
+    }
+    let Some(stop) = stop else {
+        return format!("{} returns {}", hit.source, describe(hit));
+    };
+    let stop_answer = winner(candidates, stop.index).map(|c| format!(" with {}", describe(c))).unwrap_or_default();
+
+    if hit.index > stop.index {
+        return match &stop.criterion {
+            Some(criterion) => format!(
+                "{} would return {}, but {} returned {} and {} ended the lookup before {} was consulted",
+                hit.source,
+                describe(hit),
+                stop.source,
+                stop.status,
+                criterion,
+                hit.source
+            ),
+            None => format!(
+                "{} would return {}, but an earlier source matched: {} returned {}{} and the default action for {} is return",
+                hit.source,
+                describe(hit),
+                stop.source,
+                stop.status,
+                stop_answer,
+                stop.status
+            ),
+        };
+    }
+
+    match winner(candidates, hit.index) {
+        Some(first) if first.query != hit.query => format!(
+            "a search suffix matched first: {} answered {} for {} before {} was tried",
+            hit.source,
+            first.address,
+            first.query.as_deref().unwrap_or("the name as given"),
+            hit.query.as_deref().unwrap_or("the name as given")
+        ),
+        Some(first) if !same_address(&first.address, &hit.address) => format!(
+            "{} returns {} too, but {} comes first and is the answer shown",
+            hit.source,
+            describe(hit),
+            describe(first)
+        ),
+        _ => format!("{} returns {}", hit.source, describe(hit)),
+    }
+}
+
+/// Explains why `expected` is not the answer for a host lookup. `steps` and
+/// `candidates` cover every source, including those after the walk stopped
+/// at `stop`.
+pub fn explain_why_not(
+    expected: &str,
+    result: Option<&str>,
+    steps: &[DecisionStep],
+    candidates: &[Candidate],
+    stop: Option<&Stop>,
+) -> WhyNot {
+    let answered_by = result.and_then(|result| {
+        steps
+            .iter()
+            .rev()
+            .find(|step| matches!(&step.outcome, Outcome::Match(value) if value == result))
+            .map(|step| step.source.clone())
+    });
+
+    if result.is_some_and(|result| same_address(result, expected)) {
+        return WhyNot {
+            expected: expected.to_string(),
+            produced: true,
+            explanation: vec![format!("{} is the answer, from {}", expected, answered_by.unwrap_or_default())],
+        };
+    }
+
+    let mut explanation = Vec::new();
+    match (result, &answered_by) {
+        (Some(result), Some(source)) => explanation.push(format!("The answer is {} from {}", result, source)),
+        _ => explanation.push("The lookup produced no answer".to_string()),
+    }
+
+    let hits: Vec<&Candidate> = candidates.iter().filter(|c| same_address(&c.address, expected)).collect();
+    if hits.is_empty() {
+        explanation.push(format!("No source produces {}, including sources after the lookup stopped", expected));
+        let mut described: Vec<&str> = Vec::new();
+        for step in steps.iter().filter(|step| !matches!(step.outcome, Outcome::Info(_))) {
+            let source = step.source.split_whitespace().next().unwrap_or_default();
+            if described.contains(&source) {
+                continue;
+            }
+            described.push(source);
+            let addresses: Vec<String> = candidates.iter().filter(|c| c.source == source).map(describe).collect();
+            if addresses.is_empty() {
+                explanation.push(format!("{} gave {}: {}", step.source, outcome_label(&step.outcome), step.reason));
+            } else {
+                explanation.push(format!("{} returns {}", source, addresses.join(", ")));
+            }
+        }
+        return WhyNot {
+            expected: expected.to_string(),
+            produced: false,
+            explanation,
+        };
+    }
+
+    explanation.extend(hits.iter().map(|hit| explain_hit(hit, candidates, stop)));
+    WhyNot {
+        expected: expected.to_string(),
+        produced: false,
+        explanation,
+    }
+}
@@ -0,0 +1,22 @@
+//! The resolution engine behind the `resolve` command: nsswitch parsing,
+//! the sources it names, and the explanations of how a name was resolved.
+//! Embed it through [`Resolver`], registering your own [`Source`]s in a
+//! [`Registry`] next to the built-in ones.
+
+pub mod audit;
+pub mod batch;
+pub mod check;
+pub mod context;
+pub mod dbus;
+pub mod dns;
+pub mod explain;
+pub mod nss;
+pub mod proc;
+pub mod render;
+pub mod resolver;
+pub mod sources;
+pub mod sysroot;
+
+pub use resolver::Resolver;
+pub use sources::registry::Registry;
+pub use sources::source::Source;
@@ -1,273 +1,232 @@
 mod cli;
-mod nss;
-mod dns;
-mod proc;
-mod explain;
 
 use clap::Parser;
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Serialize, Deserialize)]
-struct ResolutionResult {
-    name: String,
-    result: Option<String>,
-    steps: Vec<explain::decision_tree::DecisionStep>,
-}
+use resolve::{audit, batch, check, context, explain, proc, render, resolver, sysroot};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = cli::Cli::parse();
-
-    match cli.command {
-        cli::Command::Host { name, pid, why } => {
-            let result = resolve_host(&name, pid, why).await?;
-            if cli.json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
-            } else {
-                if let Some(ip) = &result.result {
-                    println!("{} → {}", name, ip);
-                } else {
-                    println!("{} not resolved", name);
-                }
-                if why {
-                    println!("Resolution path:");
-                    for (i, step) in result.steps.iter().enumerate() {
-                        let outcome_str = match &step.outcome {
-                            explain::decision_tree::Outcome::Match(ip) => format!("Match: {}", ip),
-                            explain::decision_tree::Outcome::NoMatch => "No match".to_string(),
-                            explain::decision_tree::Outcome::Error(e) => format!("Error: {}", e),
-                            explain::decision_tree::Outcome::Unsupported(r) => format!("Unsupported: {}", r),
-                        };
-                        println!("  {}. {} → {}", i + 1, step.source, outcome_str);
-                        if !step.reason.is_empty() {
-                            println!("     Reason: {}", step.reason);
-                        }
-                    }
-                }
-            }
+    let resolver = Arc::new(resolver::Resolver::new(registry(&cli.plugin_dir)));
+    let format = render::renderer::select(cli.format, cli.json);
+
+    let (output, failed) = match cli.command {
+        cli::Command::Host {
+            name,
+            pid,
+            why,
+            expect,
+            why_not,
+            all_sources,
+        } => {
+            let ctx = build_context(pid, &cli.target)?;
+            let options = resolver::Options {
+                all_sources,
+                expect: expect.clone().filter(|_| why_not),
+                // Graphs cluster DNS sub-steps per nameserver
+                per_server: matches!(format, render::renderer::OutputFormat::Dot | render::renderer::OutputFormat::Mermaid),
+            };
+            let result = resolver.resolve_host(&name, &ctx, &options).await;
+            let expected = expect.as_deref();
+            let failed = expected.is_some_and(|expected| !result.result.as_deref().is_some_and(|ip| explain::why_not::same_address(ip, expected)));
+            let output = render::output::Output::Lookup {
+                database: render::output::Database::Hosts,
+                result: Box::new(result),
+                why,
+            };
+            (output, failed)
         }
-        cli::Command::User { name, pid, why } => {
-            let result = resolve_user(&name, pid).await?;
-            if cli.json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
-            } else {
-                if let Some(uid) = &result.result {
-                    println!("{} → uid {}", name, uid);
-                } else {
-                    println!("{} not found", name);
-                }
-                if why {
-                    println!("Resolution path:");
-                    for (i, step) in result.steps.iter().enumerate() {
-                        let outcome_str = match &step.outcome {
-                            explain::decision_tree::Outcome::Match(uid) => format!("Match: {}", uid),
-                            explain::decision_tree::Outcome::NoMatch => "No match".to_string(),
-                            explain::decision_tree::Outcome::Error(e) => format!("Error: {}", e),
-                            explain::decision_tree::Outcome::Unsupported(r) => format!("Unsupported: {}", r),
-                        };
-                        println!("  {}. {} → {}", i + 1, step.source, outcome_str);
-                        if !step.reason.is_empty() {
-                            println!("     Reason: {}", step.reason);
-                        }
-                    }
-                }
+        cli::Command::User { name, pid, why, all_sources } => {
+            let options = resolver::Options { all_sources, ..Default::default() };
+            let result = resolver.resolve(render::output::Database::Passwd, &name, &build_context(pid, &cli.target)?, &options).await;
+            let output = render::output::Output::Lookup {
+                database: render::output::Database::Passwd,
+                result: Box::new(result),
+                why,
+            };
+            (output, false)
+        }
+        cli::Command::Group { name, pid, why, all_sources } => {
+            let options = resolver::Options { all_sources, ..Default::default() };
+            let result = resolver.resolve(render::output::Database::Group, &name, &build_context(pid, &cli.target)?, &options).await;
+            let output = render::output::Output::Lookup {
+                database: render::output::Database::Group,
+                result: Box::new(result),
+                why,
+            };
+            (output, false)
+        }
+        cli::Command::Snapshot { output, pid, host, user, group } => {
+            let ctx = build_context(pid, &cli.target)?;
+            let names = [
+                (render::output::Database::Hosts, host.as_slice()),
+                (render::output::Database::Passwd, user.as_slice()),
+                (render::output::Database::Group, group.as_slice()),
+            ];
+            let (manifest, files) = sysroot::snapshot::capture(&resolver, &ctx, &names).await;
+            let (file_count, answer_count) = (files.len(), manifest.answers.len());
+            sysroot::snapshot::write_bundle(&output, manifest, files)?;
+            let summary = render::output::SnapshotSummary {
+                bundle: output.display().to_string(),
+                files: file_count,
+                answers: answer_count,
+            };
+            (render::output::Output::Snapshot(summary), false)
+        }
+        cli::Command::Audit { command } => {
+            let (suite, pid) = match command {
+                cli::AuditCommand::Identities { pid } => ("identities", pid),
+                cli::AuditCommand::Dns { pid } => ("dns", pid),
+            };
+            let ctx = build_context(pid, &cli.target)?;
+            let report = match command {
+                cli::AuditCommand::Identities { .. } => audit::identities::audit_identities(&ctx),
+                cli::AuditCommand::Dns { .. } => audit::dns::audit_dns(&ctx),
+            };
+            let output = render::output::Output::Audit {
+                suite: suite.to_string(),
+                report,
+                root_uri: root_uri(&ctx, &cli.target),
+            };
+            (output, false)
+        }
+        cli::Command::Check { file, pid } => {
+            let expectations = check::expectations::load_expectations(&file)?;
+            let ctx = build_context(pid, &cli.target)?;
+            let mut results = Vec::new();
+            for located in &expectations.hosts {
+                let result = resolver.resolve(render::output::Database::Hosts, &located.expectation.name, &ctx, &Default::default()).await;
+                results.push(check::expectations::check_host(located, result.result.as_deref(), &result.steps));
+            }
+            for located in &expectations.users {
+                let result = resolver.resolve(render::output::Database::Passwd, &located.expectation.name, &ctx, &Default::default()).await;
+                results.push(check::expectations::check_user(located, result.result.as_deref(), &result.steps));
+            }
+            for located in &expectations.groups {
+                let result = resolver.resolve(render::output::Database::Group, &located.expectation.name, &ctx, &Default::default()).await;
+                results.push(check::expectations::check_group(located, result.result.as_deref(), &result.steps, result.record.as_ref()));
             }
+            let report = check::expectations::CheckReport {
+                file: expectations.path,
+                results,
+            };
+            let base = if file.is_absolute() { std::path::PathBuf::from("/") } else { std::env::current_dir()? };
+            let base_uri = format!("file://{}/", base.display().to_string().trim_end_matches('/'));
+            let failed = !report.passed();
+            (render::output::Output::Check { report, base_uri }, failed)
         }
-        cli::Command::Group { name, pid, why } => {
-            let result = resolve_group(&name, pid).await?;
-            if cli.json {
-                println!("{}", serde_json::to_string_pretty(&result)?);
-            } else {
-                if let Some(gid) = &result.result {
-                    println!("{} → gid {}", name, gid);
-                } else {
-                    println!("{} not found", name);
-                }
-                if why {
-                    println!("Resolution path:");
-                    for (i, step) in result.steps.iter().enumerate() {
-                        let outcome_str = match &step.outcome {
-                            explain::decision_tree::Outcome::Match(gid) => format!("Match: {}", gid),
-                            explain::decision_tree::Outcome::NoMatch => "No match".to_string(),
-                            explain::decision_tree::Outcome::Error(e) => format!("Error: {}", e),
-                            explain::decision_tree::Outcome::Unsupported(r) => format!("Unsupported: {}", r),
-                        };
-                        println!("  {}. {} → {}", i + 1, step.source, outcome_str);
-                        if !step.reason.is_empty() {
-                            println!("     Reason: {}", step.reason);
-                        }
-                    }
+        cli::Command::Batch {
+            kind,
+            file,
+            input,
+            concurrency,
+            pid,
+        } => {
+            if !matches!(cli.format, None | Some(render::renderer::OutputFormat::Json | render::renderer::OutputFormat::Ndjson)) {
+                anyhow::bail!("resolve batch always prints NDJSON");
+            }
+            let database = match kind {
+                cli::BatchType::Host => render::output::Database::Hosts,
+                cli::BatchType::User => render::output::Database::Passwd,
+                cli::BatchType::Group => render::output::Database::Group,
+            };
+            let (reader, format): (Box<dyn std::io::BufRead>, _) = match file.filter(|file| file.as_os_str() != "-") {
+                Some(file) => {
+                    let opened = std::fs::File::open(&file).map_err(|e| anyhow::anyhow!("Cannot read {}: {}", file.display(), e))?;
+                    let format = input.unwrap_or_else(|| batch::input::InputFormat::from_path(&file));
+                    (Box::new(std::io::BufReader::new(opened)), format)
                 }
+                None => (Box::new(std::io::stdin().lock()), input.unwrap_or(batch::input::InputFormat::Lines)),
+            };
+            let names = batch::input::read_names(reader, format)?;
+            // One context and one resolver for every name: the target is
+            // detected or loaded once and the D-Bus connection is reused
+            let ctx = Arc::new(build_context(pid, &cli.target)?.with_parsed_config());
+            let mut stdout = std::io::stdout().lock();
+            let summary = match batch::runner::run_batch(resolver.clone(), ctx, database, names, concurrency.into(), &mut stdout).await {
+                Ok(summary) => summary,
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            if summary.failed() > 0 {
+                std::process::exit(1);
             }
+            return Ok(());
         }
+        cli::Command::Schema => (render::output::Output::Schema(render::schema::lookup_schema()), false),
         cli::Command::Diff { pid: _pid, pid2: _pid2 } => {
             // TODO: Implement diff
             println!("Diff not implemented yet");
+            return Ok(());
+        }
+    };
+
+    if let Err(e) = render::renderer::for_format(format).render(&output, &mut std::io::stdout().lock()) {
+        // A reader such as `head` closing the pipe early is not a failure
+        if e.downcast_ref::<std::io::Error>().is_none_or(|e| e.kind() != std::io::ErrorKind::BrokenPipe) {
+            return Err(e);
+        }
+    }
+    // getent exits with 2 when the key is not found
+    if let render::output::Output::Lookup { database, result, .. } = &output {
+        if format == render::renderer::OutputFormat::Getent && render::getent::getent_lines(*database, result).is_ok_and(|lines| lines.is_empty()) {
+            std::process::exit(2);
         }
     }
+    if failed {
+        std::process::exit(1);
+    }
 
     Ok(())
 }
 
-async fn resolve_user(name: &str, _pid: Option<u32>) -> anyhow::Result<ResolutionResult> {
-    let mut steps = Vec::new();
-
-    // For simplicity, parse /etc/passwd
-    let users = nss::passwd::parse_passwd_file("/etc/passwd").unwrap_or_default();
-    let matches = nss::passwd::resolve_user_from_passwd(name, &users);
-    if let Some(entry) = matches.first() {
-        steps.push(explain::decision_tree::DecisionStep {
-            source: "files (/etc/passwd)".to_string(),
-            outcome: explain::decision_tree::Outcome::Match(entry.uid.to_string()),
-            reason: "Found in passwd file".to_string(),
-        });
-        return Ok(ResolutionResult {
-            name: name.to_string(),
-            result: Some(entry.uid.to_string()),
-            steps,
-        });
+/// Members listed by the entry that answered a group lookup. Only the files
+/// source exposes member lists.
+/// The built-in sources, plus plugins from `--plugin-dir` or $RESOLVE_PLUGIN_PATH.
+fn registry(plugin_dirs: &[std::path::PathBuf]) -> resolve::Registry {
+    let mut registry = resolve::Registry::builtin();
+    if plugin_dirs.is_empty() {
+        if let Some(path) = std::env::var_os("RESOLVE_PLUGIN_PATH") {
+            std::env::split_paths(&path).filter(|dir| !dir.as_os_str().is_empty()).for_each(|dir| registry.add_plugin_dir(dir));
+        }
     } else {
-        steps.push(explain::decision_tree::DecisionStep {
-            source: "files (/etc/passwd)".to_string(),
-            outcome: explain::decision_tree::Outcome::NoMatch,
-            reason: "Not found in passwd file".to_string(),
-        });
+        plugin_dirs.iter().for_each(|dir| registry.add_plugin_dir(dir));
     }
-
-    Ok(ResolutionResult {
-        name: name.to_string(),
-        result: None,
-        steps,
-    })
+    registry
 }
 
-async fn resolve_group(name: &str, _pid: Option<u32>) -> anyhow::Result<ResolutionResult> {
-    let mut steps = Vec::new();
-
-    // For simplicity, parse /etc/group
-    let groups = nss::group::parse_group_file("/etc/group").unwrap_or_default();
-    let matches = nss::group::resolve_group_from_group(name, &groups);
-    if let Some(entry) = matches.first() {
-        steps.push(explain::decision_tree::DecisionStep {
-            source: "files (/etc/group)".to_string(),
-            outcome: explain::decision_tree::Outcome::Match(entry.gid.to_string()),
-            reason: "Found in group file".to_string(),
-        });
-        return Ok(ResolutionResult {
-            name: name.to_string(),
-            result: Some(entry.gid.to_string()),
-            steps,
-        });
-    } else {
-        steps.push(explain::decision_tree::DecisionStep {
-            source: "files (/etc/group)".to_string(),
-            outcome: explain::decision_tree::Outcome::NoMatch,
-            reason: "Not found in group file".to_string(),
-        });
+/// The `file://` URI of the directory findings are relative to: the `--root`
+/// directory, or `/` for the running system. Images and snapshots have none.
+fn root_uri(ctx: &context::ResolveContext, target: &cli::TargetArgs) -> Option<String> {
+    if let Some(root) = &target.root {
+        let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.clone());
+        return Some(format!("file://{}/", root.display().to_string().trim_end_matches('/')));
     }
-
-    Ok(ResolutionResult {
-        name: name.to_string(),
-        result: None,
-        steps,
-    })
+    ctx.is_live().then(|| "file:///".to_string())
 }
 
-async fn resolve_host(name: &str, _pid: Option<u32>, _why: bool) -> anyhow::Result<ResolutionResult> {
-    let mut steps = Vec::new();
-
-    // Parse nsswitch
-    let mut nss_order = nss::nsswitch::parse_nsswitch_file("/etc/nsswitch.conf").unwrap_or_default();
-    if nss_order.hosts.is_empty() {
-        nss_order.hosts = vec!["files".to_string(), "dns".to_string()];
-    }
-
-    for source in &nss_order.hosts {
-        match source.as_str() {
-            "files" => {
-                let hosts = nss::hosts::parse_hosts_file("/etc/hosts").unwrap_or_default();
-                let matches = nss::hosts::resolve_host_from_hosts(name, &hosts);
-                if let Some(entry) = matches.first() {
-                    steps.push(explain::decision_tree::DecisionStep {
-                        source: "files (/etc/hosts)".to_string(),
-                        outcome: explain::decision_tree::Outcome::Match(entry.ip.clone()),
-                        reason: "Found in hosts file".to_string(),
-                    });
-                    return Ok(ResolutionResult {
-                        name: name.to_string(),
-                        result: Some(entry.ip.clone()),
-                        steps,
-                    });
-                } else {
-                    steps.push(explain::decision_tree::DecisionStep {
-                        source: "files (/etc/hosts)".to_string(),
-                        outcome: explain::decision_tree::Outcome::NoMatch,
-                        reason: "Not found in hosts file".to_string(),
-                    });
-                }
-            }
-            "dns" => {
-                // Try systemd-resolved if Linux
-                steps.push(explain::decision_tree::DecisionStep {
-                    source: "dns (systemd-resolved)".to_string(),
-                    outcome: if cfg!(target_os = "linux") {
-                        match dns::resolved::resolve_hostname_via_resolved(name).await {
-                            Ok(ip) => explain::decision_tree::Outcome::Match(ip),
-                            Err(e) => explain::decision_tree::Outcome::Error(format!("DBus error: {}", e)),
-                        }
-                    } else {
-                        explain::decision_tree::Outcome::Unsupported("systemd-resolved is Linux-only".to_string())
-                    },
-                    reason: if cfg!(target_os = "linux") { "Attempted systemd-resolved" } else { "Skipped on non-Linux" }.to_string(),
-                });
-
-                // If not matched, try libc DNS
-                if !matches!(steps.last().unwrap().outcome, explain::decision_tree::Outcome::Match(_)) {
-                    match dns::resolved::resolve_hostname_libc(name).await {
-                        Ok(ip) => {
-                            steps.push(explain::decision_tree::DecisionStep {
-                                source: "dns (libc)".to_string(),
-                                outcome: explain::decision_tree::Outcome::Match(ip.clone()),
-                                reason: "Resolved using system resolver".to_string(),
-                            });
-                            return Ok(ResolutionResult {
-                                name: name.to_string(),
-                                result: Some(ip),
-                                steps,
-                            });
-                        }
-                        Err(e) => {
-                            steps.push(explain::decision_tree::DecisionStep {
-                                source: "dns (libc)".to_string(),
-                                outcome: explain::decision_tree::Outcome::Error(format!("Libc error: {}", e)),
-                                reason: "Failed to resolve via libc".to_string(),
-                            });
-                        }
-                    }
-                } else {
-                    // If systemd matched, return it
-                    if let explain::decision_tree::Outcome::Match(ip) = &steps.last().unwrap().outcome {
-                        return Ok(ResolutionResult {
-                            name: name.to_string(),
-                            result: Some(ip.clone()),
-                            steps,
-                        });
-                    }
-                }
-            }
-            _ => {
-                steps.push(explain::decision_tree::DecisionStep {
-                    source: format!("{} (unsupported)", source),
-                    outcome: explain::decision_tree::Outcome::NoMatch,
-                    reason: "Source not implemented".to_string(),
-                });
+fn build_context(pid: Option<u32>, target: &cli::TargetArgs) -> anyhow::Result<context::ResolveContext> {
+    let sysroot = if let Some(root) = &target.root {
+        sysroot::Sysroot::dir(root).map_err(|e| anyhow::anyhow!("Cannot use root {}: {}", root.display(), e))?
+    } else if let Some(image) = &target.image {
+        sysroot::image::load_image(image).map_err(|e| anyhow::anyhow!("Cannot load image {}: {}", image.display(), e))?
+    } else if let Some(bundle) = &target.from_snapshot {
+        sysroot::snapshot::load_snapshot(bundle)
+            .map_err(|e| anyhow::anyhow!("Cannot load snapshot {}: {}", bundle.display(), e))?
+    } else {
+        if let Some(name) = &target.netns {
+            // `ip netns exec` fails the same way; without the handle there is no namespace to resolve in
+            let known = proc::namespaces::netns_names();
+            if name.contains('/') || !known.contains(name) {
+                let known = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+                anyhow::bail!("No network namespace named {} in {} (known: {})", name, proc::namespaces::NETNS_RUN_DIR, known);
             }
         }
+        return Ok(context::ResolveContext::new(pid, target.netns.clone()));
+    };
+    if pid.is_some() {
+        anyhow::bail!("--pid cannot be combined with --root, --image or --from-snapshot");
     }
-
-    Ok(ResolutionResult {
-        name: name.to_string(),
-        result: None,
-        steps,
-    })
-}
\ No newline at end of file
+    Ok(context::ResolveContext::from_sysroot(sysroot))
+}
@@ -2,22 +2,34 @@ use std::fs;
 use std::io::{self, BufRead};
 use std::path::Path;
 
+use super::passwd;
+
 #[derive(Debug, Clone)]
 pub struct GroupEntry {
     pub name: String,
+    pub password: String,
     pub gid: u32,
-    #[allow(dead_code)]
     pub members: Vec<String>,
     #[allow(dead_code)]
     pub source: String,
+    pub line: usize,
+}
+
+impl GroupEntry {
+    /// The entry as `getent group` prints it.
+    pub fn getent_line(&self) -> String {
+        format!("{}:{}:{}:{}", self.name, self.password, self.gid, self.members.join(","))
+    }
 }
 
 pub fn parse_group_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<GroupEntry>> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
+    parse_group(io::BufReader::new(fs::File::open(path)?))
+}
+
+pub fn parse_group<R: BufRead>(reader: R) -> io::Result<Vec<GroupEntry>> {
     let mut entries = Vec::new();
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line?;
         let line = line.trim();
 
@@ -31,8 +43,13 @@ pub fn parse_group_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<GroupEntry>>
             continue;
         }
 
+        // glibc ignores lines whose gid is not a number
+        let Some(gid) = passwd::parse_id(parts[2]) else {
+            continue;
+        };
+
         let name = parts[0].to_string();
-        let gid = parts[2].parse().unwrap_or(0);
+        let password = parts[1].to_string();
         let members: Vec<String> = if parts[3].is_empty() {
             Vec::new()
         } else {
@@ -41,9 +58,11 @@ pub fn parse_group_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<GroupEntry>>
 
         entries.push(GroupEntry {
             name,
+            password,
             gid,
             members,
             source: "/etc/group".to_string(),
+            line: index + 1,
         });
     }
 
@@ -1,6 +1,5 @@
-use std::fs;
 use std::io::{self, BufRead};
-use std::path::Path;
+use std::net::IpAddr;
 
 #[derive(Debug, Clone)]
 pub struct HostEntry {
@@ -8,14 +7,13 @@ pub struct HostEntry {
     pub names: Vec<String>,
     #[allow(dead_code)]
     pub source: String, // "/etc/hosts"
+    pub line: usize,
 }
 
-pub fn parse_hosts_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<HostEntry>> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
+pub fn parse_hosts<R: BufRead>(reader: R) -> io::Result<Vec<HostEntry>> {
     let mut entries = Vec::new();
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line?;
         let line = line.trim();
 
@@ -36,6 +34,7 @@ pub fn parse_hosts_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<HostEntry>> {
             ip,
             names,
             source: "/etc/hosts".to_string(),
+            line: index + 1,
         });
     }
 
@@ -47,4 +46,32 @@ pub fn resolve_host_from_hosts<'a>(name: &str, entries: &'a [HostEntry]) -> Vec<
         .iter()
         .filter(|entry| entry.names.contains(&name.to_string()))
         .collect()
-}
\ No newline at end of file
+}
+
+/// The entry `getent hosts name` prints: gethostbyname2 is asked for
+/// AF_INET6 first and AF_INET only when no IPv6 line matches. With glibc's
+/// default `multi on`, the first line names the host and every matching line
+/// of that family adds its aliases.
+pub fn getent_host_entry(name: &str, entries: &[HostEntry]) -> Option<HostEntry> {
+    let matches = resolve_host_from_hosts(name, entries);
+    let is_v6 = |entry: &&HostEntry| entry.ip.parse::<IpAddr>().is_ok_and(|ip| ip.is_ipv6());
+    let family: Vec<&HostEntry> = if matches.iter().any(is_v6) {
+        matches.into_iter().filter(is_v6).collect()
+    } else {
+        matches.into_iter().filter(|entry| !is_v6(entry)).collect()
+    };
+    let mut merged = (*family.first()?).clone();
+    for entry in &family[1..] {
+        for alias in &entry.names {
+            if !merged.names.contains(alias) {
+                merged.names.push(alias.clone());
+            }
+        }
+    }
+    Some(merged)
+}
+
+/// The first line for `address`, as gethostbyaddr reads /etc/hosts.
+pub fn resolve_address_from_hosts<'a>(address: &IpAddr, entries: &'a [HostEntry]) -> Option<&'a HostEntry> {
+    entries.iter().find(|entry| entry.ip.parse::<IpAddr>().is_ok_and(|ip| ip == *address))
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::context::ResolveContext;
+
+pub const DNSMASQ_DIR: &str = "/var/lib/libvirt/dnsmasq";
+
+#[derive(Debug, Clone, Deserialize)]
+struct StatusRecord {
+    #[serde(rename = "ip-address")]
+    ip_address: String,
+    #[serde(rename = "mac-address")]
+    mac_address: Option<String>,
+    hostname: Option<String>,
+    #[serde(rename = "expiry-time")]
+    expiry_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MacsRecord {
+    domain: String,
+    #[serde(default)]
+    macs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LibvirtLease {
+    pub ip: String,
+    pub mac: Option<String>,
+    pub hostname: Option<String>,
+    /// Seconds since the epoch; 0 means the lease never expires.
+    pub expiry_time: i64,
+    pub network: String,
+    pub source: String,
+}
+
+impl LibvirtLease {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry_time != 0 && self.expiry_time < now
+    }
+
+    pub fn describe(&self, now: i64) -> String {
+        let mac = self.mac.as_deref().unwrap_or("unknown MAC");
+        let expiry = if self.expiry_time == 0 {
+            "never expires".to_string()
+        } else if self.is_expired(now) {
+            format!("expired {}s ago", now - self.expiry_time)
+        } else {
+            format!("expires in {}s", self.expiry_time - now)
+        };
+        format!("lease {} for {} on network {} ({}), from {}", self.ip, mac, self.network, expiry, self.source)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LibvirtDomain {
+    pub name: String,
+    pub macs: Vec<String>,
+    pub source: String,
+}
+
+/// Maps bridge interfaces to libvirt network names using the `interface=`
+/// line of each network's dnsmasq config (e.g. default.conf → virbr0).
+fn network_names(ctx: &ResolveContext, files: &[String]) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for file in files {
+        let Some(network) = file.strip_suffix(".conf") else {
+            continue;
+        };
+        let content = ctx.read_to_string(&format!("{}/{}", DNSMASQ_DIR, file)).unwrap_or_default();
+        for line in content.lines() {
+            if let Some(bridge) = line.trim().strip_prefix("interface=") {
+                names.insert(bridge.to_string(), network.to_string());
+            }
+        }
+    }
+    names
+}
+
+pub fn parse_status(content: &str, network: &str, source: &str) -> io::Result<Vec<LibvirtLease>> {
+    // dnsmasq's leaseshelper leaves an empty file when there are no leases
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let records: Vec<StatusRecord> =
+        serde_json::from_str(content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| LibvirtLease {
+            ip: record.ip_address,
+            mac: record.mac_address,
+            hostname: record.hostname,
+            expiry_time: record.expiry_time.unwrap_or(0),
+            network: network.to_string(),
+            source: source.to_string(),
+        })
+        .collect())
+}
+
+pub fn parse_macs(content: &str, source: &str) -> io::Result<Vec<LibvirtDomain>> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let records: Vec<MacsRecord> =
+        serde_json::from_str(content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| LibvirtDomain {
+            name: record.domain,
+            macs: record.macs,
+            source: source.to_string(),
+        })
+        .collect())
+}
+
+/// Reads every `<bridge>.status` file in the dnsmasq directory, labelling
+/// leases with the libvirt network that owns the bridge.
+pub fn load_leases(ctx: &ResolveContext) -> io::Result<Vec<LibvirtLease>> {
+    let files = ctx.read_dir(DNSMASQ_DIR)?;
+    let networks = network_names(ctx, &files);
+    let mut leases = Vec::new();
+
+    for file in &files {
+        let Some(bridge) = file.strip_suffix(".status") else {
+            continue;
+        };
+        let network = match networks.get(bridge) {
+            Some(network) => format!("{} ({})", network, bridge),
+            None => bridge.to_string(),
+        };
+        let path = format!("{}/{}", DNSMASQ_DIR, file);
+        leases.extend(parse_status(&ctx.read_to_string(&path)?, &network, &ctx.describe(&path))?);
+    }
+
+    Ok(leases)
+}
+
+pub fn load_domains(ctx: &ResolveContext) -> io::Result<Vec<LibvirtDomain>> {
+    let mut domains = Vec::new();
+    for file in ctx.read_dir(DNSMASQ_DIR)?.iter().filter(|f| f.ends_with(".macs")) {
+        let path = format!("{}/{}", DNSMASQ_DIR, file);
+        domains.extend(parse_macs(&ctx.read_to_string(&path)?, &ctx.describe(&path))?);
+    }
+    Ok(domains)
+}
+
+/// `libnss_libvirt`: match the hostname the guest sent in its DHCP request.
+pub fn leases_for_hostname<'a>(name: &str, leases: &'a [LibvirtLease]) -> Vec<&'a LibvirtLease> {
+    leases
+        .iter()
+        .filter(|lease| lease.hostname.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(name)))
+        .collect()
+}
+
+/// `libnss_libvirt_guest`: match the libvirt domain name, then its leases by MAC.
+pub fn leases_for_domain<'a>(name: &str, domains: &[LibvirtDomain], leases: &'a [LibvirtLease]) -> Vec<&'a LibvirtLease> {
+    let macs: Vec<String> = domains
+        .iter()
+        .filter(|domain| domain.name == name)
+        .flat_map(|domain| domain.macs.iter().map(|mac| mac.to_ascii_lowercase()))
+        .collect();
+
+    leases
+        .iter()
+        .filter(|lease| lease.mac.as_ref().is_some_and(|mac| macs.contains(&mac.to_ascii_lowercase())))
+        .collect()
+}
@@ -0,0 +1,269 @@
+use std::io::{self, BufRead};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+use crate::dns::wire;
+
+pub const MDNS_GROUP: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+pub const MDNS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdnsFamily {
+    Any,
+    V4,
+    V6,
+}
+
+/// One of the host modules shipped by nss-mdns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MdnsModule {
+    pub family: MdnsFamily,
+    pub minimal: bool,
+}
+
+impl MdnsModule {
+    pub fn from_source(source: &str) -> Option<MdnsModule> {
+        let (family, minimal) = match source {
+            "mdns" => (MdnsFamily::Any, false),
+            "mdns4" => (MdnsFamily::V4, false),
+            "mdns6" => (MdnsFamily::V6, false),
+            "mdns_minimal" => (MdnsFamily::Any, true),
+            "mdns4_minimal" => (MdnsFamily::V4, true),
+            "mdns6_minimal" => (MdnsFamily::V6, true),
+            _ => return None,
+        };
+        Some(MdnsModule { family, minimal })
+    }
+
+    fn accepts(&self, addr: &IpAddr) -> bool {
+        match self.family {
+            MdnsFamily::Any => true,
+            MdnsFamily::V4 => addr.is_ipv4(),
+            MdnsFamily::V6 => addr.is_ipv6(),
+        }
+    }
+
+    fn query_types(&self) -> Vec<u16> {
+        match self.family {
+            MdnsFamily::Any => vec![wire::TYPE_A, wire::TYPE_AAAA],
+            MdnsFamily::V4 => vec![wire::TYPE_A],
+            MdnsFamily::V6 => vec![wire::TYPE_AAAA],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MdnsPolicy {
+    Allowed(String),
+    Refused(String),
+}
+
+pub fn parse_mdns_allow<R: BufRead>(reader: R) -> io::Result<Vec<String>> {
+    let mut domains = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        domains.push(line.trim_matches('.').to_ascii_lowercase());
+    }
+
+    Ok(domains)
+}
+
+fn under_domain(name: &str, domain: &str) -> bool {
+    name == domain || name.ends_with(&format!(".{}", domain))
+}
+
+/// Decides whether nss-mdns will query a name at all. The `_minimal`
+/// modules only ever handle `.local`; the full modules consult
+/// /etc/mdns.allow when it exists and fall back to `.local` otherwise.
+pub fn mdns_policy(module: &MdnsModule, name: &str, allow: Option<&[String]>) -> MdnsPolicy {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+
+    if module.minimal || allow.is_none() {
+        let why = if module.minimal {
+            "minimal module only handles .local"
+        } else {
+            "/etc/mdns.allow does not exist, so only .local is handled"
+        };
+        return if under_domain(&name, "local") {
+            MdnsPolicy::Allowed(format!("{} is under .local ({})", name, why))
+        } else {
+            MdnsPolicy::Refused(format!("{} is not under .local ({})", name, why))
+        };
+    }
+
+    let allow = allow.unwrap_or_default();
+    if allow.iter().any(|d| d == "*") {
+        return MdnsPolicy::Allowed("/etc/mdns.allow contains \"*\"".to_string());
+    }
+    match allow.iter().find(|d| under_domain(&name, d)) {
+        Some(domain) => MdnsPolicy::Allowed(format!("{} matches .{} in /etc/mdns.allow", name, domain)),
+        None => MdnsPolicy::Refused(format!("{} matches no domain in /etc/mdns.allow", name)),
+    }
+}
+
+/// Sends a one-shot multicast DNS query (RFC 6762 §5.1) and collects the
+/// address records returned for `name` until `timeout` elapses.
+pub async fn query_mdns(module: &MdnsModule, name: &str, target: SocketAddr, timeout: Duration) -> anyhow::Result<Vec<IpAddr>> {
+    let questions: Vec<wire::Question> = module
+        .query_types()
+        .into_iter()
+        .map(|qtype| wire::Question {
+            name: name.trim_end_matches('.').to_string(),
+            qtype,
+            unicast_response: true,
+        })
+        .collect();
+    let query = wire::build_query(0, false, &questions).map_err(|e| anyhow::anyhow!(e))?;
+
+    let bind: SocketAddr = if target.is_ipv4() { "0.0.0.0:0".parse()? } else { "[::]:0".parse()? };
+    let socket = UdpSocket::bind(bind).await?;
+    if target.is_ipv4() {
+        socket.set_multicast_ttl_v4(255)?;
+    }
+    socket.send_to(&query, target).await?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = vec![0u8; 9000];
+    let mut addresses = Vec::new();
+
+    while let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, _) = received?;
+        let Some(response) = wire::parse_response(&buf[..len]) else {
+            continue;
+        };
+        if !response.is_response {
+            continue;
+        }
+        for record in response.addresses {
+            if record.name.eq_ignore_ascii_case(name.trim_end_matches('.'))
+                && module.accepts(&record.addr)
+                && !addresses.contains(&record.addr)
+            {
+                addresses.push(record.addr);
+            }
+        }
+        // Responders answer all questions at once; stop at the first useful reply
+        if !addresses.is_empty() {
+            break;
+        }
+    }
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes an mDNS response with one A or AAAA record per entry.
+    fn response(records: &[(&str, IpAddr)]) -> Vec<u8> {
+        let mut out = vec![0, 0, 0x84, 0, 0, 0];
+        out.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        for (name, addr) in records {
+            for label in name.split('.') {
+                out.push(label.len() as u8);
+                out.extend_from_slice(label.as_bytes());
+            }
+            out.push(0);
+            let (rtype, rdata) = match addr {
+                IpAddr::V4(v4) => (wire::TYPE_A, v4.octets().to_vec()),
+                IpAddr::V6(v6) => (wire::TYPE_AAAA, v6.octets().to_vec()),
+            };
+            out.extend_from_slice(&rtype.to_be_bytes());
+            out.extend_from_slice(&(wire::CLASS_IN | 0x8000).to_be_bytes());
+            out.extend_from_slice(&120u32.to_be_bytes());
+            out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            out.extend_from_slice(&rdata);
+        }
+        out
+    }
+
+    /// A loopback stand-in for the multicast group: answers the first query
+    /// it receives with `records`, and returns the query.
+    async fn responder(records: Vec<(&'static str, IpAddr)>) -> (SocketAddr, tokio::task::JoinHandle<Vec<u8>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            if !records.is_empty() {
+                socket.send_to(&response(&records), peer).await.unwrap();
+            }
+            buf.truncate(len);
+            buf
+        });
+        (addr, task)
+    }
+
+    #[tokio::test]
+    async fn collects_addresses_for_the_name() {
+        let (addr, task) = responder(vec![
+            ("printer.local", "192.168.1.20".parse().unwrap()),
+            ("other.local", "192.168.1.99".parse().unwrap()),
+            ("PRINTER.local", "fe80::20".parse().unwrap()),
+        ])
+        .await;
+        let module = MdnsModule::from_source("mdns_minimal").unwrap();
+        let addresses = query_mdns(&module, "printer.local.", addr, Duration::from_secs(2)).await.unwrap();
+        assert_eq!(addresses, vec!["192.168.1.20".parse::<IpAddr>().unwrap(), "fe80::20".parse().unwrap()]);
+
+        let query = task.await.unwrap();
+        // One question per address family, with the unicast-response bit set
+        assert_eq!(u16::from_be_bytes([query[4], query[5]]), 2);
+        assert_eq!(&query[12..27], b"\x07printer\x05local\x00");
+        assert_eq!(u16::from_be_bytes([query[29], query[30]]), wire::CLASS_IN | 0x8000);
+    }
+
+    #[tokio::test]
+    async fn family_modules_filter_addresses() {
+        let (addr, task) = responder(vec![("nas.local", "fe80::5".parse().unwrap()), ("nas.local", "10.0.0.5".parse().unwrap())]).await;
+        let module = MdnsModule::from_source("mdns4").unwrap();
+        let addresses = query_mdns(&module, "nas.local", addr, Duration::from_secs(2)).await.unwrap();
+        assert_eq!(addresses, vec!["10.0.0.5".parse::<IpAddr>().unwrap()]);
+        // Only an A question
+        let query = task.await.unwrap();
+        assert_eq!(u16::from_be_bytes([query[4], query[5]]), 1);
+        assert_eq!(u16::from_be_bytes([query[23], query[24]]), wire::TYPE_A);
+    }
+
+    #[tokio::test]
+    async fn silence_is_no_match() {
+        let (addr, task) = responder(Vec::new()).await;
+        let module = MdnsModule::from_source("mdns").unwrap();
+        let addresses = query_mdns(&module, "ghost.local", addr, Duration::from_millis(200)).await.unwrap();
+        assert!(addresses.is_empty());
+        task.await.unwrap();
+    }
+
+    #[test]
+    fn minimal_modules_only_handle_local() {
+        let minimal = MdnsModule::from_source("mdns4_minimal").unwrap();
+        let allow = vec!["corp".to_string()];
+        assert!(matches!(mdns_policy(&minimal, "printer.local", Some(&allow)), MdnsPolicy::Allowed(_)));
+        assert!(matches!(mdns_policy(&minimal, "printer.corp", Some(&allow)), MdnsPolicy::Refused(_)));
+    }
+
+    #[test]
+    fn full_modules_follow_mdns_allow() {
+        let full = MdnsModule::from_source("mdns").unwrap();
+        let allow = parse_mdns_allow(io::Cursor::new("# allowed\n.Corp.\n\nlocal\n")).unwrap();
+        assert_eq!(allow, vec!["corp", "local"]);
+        assert!(matches!(mdns_policy(&full, "nas.corp", Some(&allow)), MdnsPolicy::Allowed(_)));
+        assert!(matches!(mdns_policy(&full, "nas.example", Some(&allow)), MdnsPolicy::Refused(_)));
+        assert!(matches!(mdns_policy(&full, "nas.example", Some(&["*".to_string()])), MdnsPolicy::Allowed(_)));
+        assert!(matches!(mdns_policy(&full, "nas.example", None), MdnsPolicy::Refused(_)));
+        assert!(MdnsModule::from_source("mdns5").is_none());
+    }
+}
@@ -1,4 +1,8 @@
 pub mod hosts;
+pub mod libvirt;
+pub mod mdns;
+pub mod mymachines;
 pub mod nsswitch;
 pub mod passwd;
-pub mod group;
\ No newline at end of file
+pub mod group;
+pub mod subid;
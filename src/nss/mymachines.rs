@@ -0,0 +1,162 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{dbus_proxy, Connection};
+
+use crate::nss::group::GroupEntry;
+use crate::nss::passwd::UserEntry;
+use crate::proc::userns::{self, IdMapRange};
+
+pub const NO_SUCH_MACHINE: &str = "org.freedesktop.machine1.NoSuchMachine";
+pub const NO_SUCH_USER_MAPPING: &str = "org.freedesktop.machine1.NoSuchUserMapping";
+pub const NO_SUCH_GROUP_MAPPING: &str = "org.freedesktop.machine1.NoSuchGroupMapping";
+
+const AF_INET: i32 = 2;
+const AF_INET6: i32 = 10;
+
+#[dbus_proxy(
+    interface = "org.freedesktop.machine1.Manager",
+    default_service = "org.freedesktop.machine1",
+    default_path = "/org/freedesktop/machine1"
+)]
+trait Machine1Manager {
+    async fn list_machines(&self) -> zbus::Result<Vec<(String, String, String, OwnedObjectPath)>>;
+    async fn get_machine(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+    async fn get_machine_addresses(&self, name: &str) -> zbus::Result<Vec<(i32, Vec<u8>)>>;
+    async fn map_from_machine_user(&self, name: &str, uid: u32) -> zbus::Result<u32>;
+    async fn map_from_machine_group(&self, name: &str, gid: u32) -> zbus::Result<u32>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.machine1.Machine",
+    default_service = "org.freedesktop.machine1"
+)]
+trait Machine1Machine {
+    #[dbus_proxy(property)]
+    fn leader(&self) -> zbus::Result<u32>;
+    #[dbus_proxy(property)]
+    fn class(&self) -> zbus::Result<String>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MachineInfo {
+    pub name: String,
+    pub class: String,
+    pub service: String,
+}
+
+/// Which id database a synthesized `vu-`/`vg-` name belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineIdKind {
+    User,
+    Group,
+}
+
+impl MachineIdKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            MachineIdKind::User => "vu-",
+            MachineIdKind::Group => "vg-",
+        }
+    }
+
+    fn map_file(&self) -> &'static str {
+        match self {
+            MachineIdKind::User => "uid_map",
+            MachineIdKind::Group => "gid_map",
+        }
+    }
+}
+
+pub async fn list_machines(connection: &Connection) -> zbus::Result<Vec<MachineInfo>> {
+    let proxy = Machine1ManagerProxy::new(connection).await?;
+    Ok(proxy
+        .list_machines()
+        .await?
+        .into_iter()
+        .map(|(name, class, service, _)| MachineInfo { name, class, service })
+        .collect())
+}
+
+pub async fn machine_addresses(connection: &Connection, name: &str) -> zbus::Result<Vec<IpAddr>> {
+    let proxy = Machine1ManagerProxy::new(connection).await?;
+    let addresses = proxy.get_machine_addresses(name).await?;
+
+    Ok(addresses
+        .into_iter()
+        .filter_map(|(family, bytes)| match (family, bytes.len()) {
+            (AF_INET, 4) => Some(IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))),
+            (AF_INET6, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&bytes);
+                Some(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Splits `vu-<machine>-<uid>` / `vg-<machine>-<gid>` the way nss-mymachines
+/// does: the machine name may itself contain dashes, the id is the last part.
+pub fn parse_machine_id_name(name: &str, kind: MachineIdKind) -> Option<(String, u32)> {
+    let rest = name.strip_prefix(kind.prefix())?;
+    let (machine, id) = rest.rsplit_once('-')?;
+    if machine.is_empty() {
+        return None;
+    }
+    Some((machine.to_string(), id.parse().ok()?))
+}
+
+/// The passwd entry nss-mymachines synthesizes for a mapped container uid.
+pub fn synthesized_user(name: &str, uid: u32) -> UserEntry {
+    UserEntry {
+        name: name.to_string(),
+        password: "*".to_string(),
+        uid,
+        gid: 65534,
+        gecos: name.to_string(),
+        home: "/".to_string(),
+        shell: "/usr/sbin/nologin".to_string(),
+        source: "systemd-machined".to_string(),
+        line: 0,
+    }
+}
+
+/// The group entry nss-mymachines synthesizes for a mapped container gid.
+pub fn synthesized_group(name: &str, gid: u32) -> GroupEntry {
+    GroupEntry {
+        name: name.to_string(),
+        password: "*".to_string(),
+        gid,
+        members: Vec::new(),
+        source: "systemd-machined".to_string(),
+        line: 0,
+    }
+}
+
+pub async fn map_from_machine(connection: &Connection, machine: &str, id: u32, kind: MachineIdKind) -> zbus::Result<u32> {
+    let proxy = Machine1ManagerProxy::new(connection).await?;
+    match kind {
+        MachineIdKind::User => proxy.map_from_machine_user(machine, id).await,
+        MachineIdKind::Group => proxy.map_from_machine_group(machine, id).await,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MachineIdShift {
+    pub leader: u32,
+    pub class: String,
+    pub ranges: Vec<IdMapRange>,
+}
+
+/// Reads the id shift of a machine from its leader's uid_map/gid_map.
+pub async fn machine_id_shift(connection: &Connection, machine: &str, kind: MachineIdKind) -> zbus::Result<MachineIdShift> {
+    let manager = Machine1ManagerProxy::new(connection).await?;
+    let path = manager.get_machine(machine).await?;
+    let proxy = Machine1MachineProxy::builder(connection).path(path)?.build().await?;
+    let leader = proxy.leader().await?;
+    let class = proxy.class().await?;
+    let ranges = userns::parse_id_map_file(format!("/proc/{}/{}", leader, kind.map_file())).unwrap_or_default();
+
+    Ok(MachineIdShift { leader, class, ranges })
+}
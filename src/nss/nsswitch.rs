@@ -1,20 +1,169 @@
 use std::collections::HashMap;
-use std::fs;
 use std::io::{self, BufRead};
-use std::path::Path;
+
+use crate::explain::decision_tree::Outcome;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum NssStatus {
+    Success,
+    NotFound,
+    Unavail,
+    TryAgain,
+}
+
+impl NssStatus {
+    pub fn from_outcome(outcome: &Outcome) -> Option<NssStatus> {
+        match outcome {
+            Outcome::Match(_) => Some(NssStatus::Success),
+            Outcome::NoMatch => Some(NssStatus::NotFound),
+            Outcome::Error(_) | Outcome::Unsupported(_) => Some(NssStatus::Unavail),
+            Outcome::TryAgain(_) => Some(NssStatus::TryAgain),
+            Outcome::Info(_) => None,
+        }
+    }
+
+    fn parse(s: &str) -> Option<NssStatus> {
+        match s.to_ascii_uppercase().as_str() {
+            "SUCCESS" => Some(NssStatus::Success),
+            "NOTFOUND" => Some(NssStatus::NotFound),
+            "UNAVAIL" => Some(NssStatus::Unavail),
+            "TRYAGAIN" => Some(NssStatus::TryAgain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NssStatus::Success => "SUCCESS",
+            NssStatus::NotFound => "NOTFOUND",
+            NssStatus::Unavail => "UNAVAIL",
+            NssStatus::TryAgain => "TRYAGAIN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NssAction {
+    Return,
+    Continue,
+    Merge,
+}
+
+impl NssAction {
+    fn parse(s: &str) -> Option<NssAction> {
+        match s.to_ascii_lowercase().as_str() {
+            "return" => Some(NssAction::Return),
+            "continue" => Some(NssAction::Continue),
+            "merge" => Some(NssAction::Merge),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NssAction::Return => "return",
+            NssAction::Continue => "continue",
+            NssAction::Merge => "merge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NssCriterion {
+    pub status: NssStatus,
+    pub negated: bool,
+    pub action: NssAction,
+}
+
+impl NssCriterion {
+    fn applies_to(&self, status: NssStatus) -> bool {
+        (self.status == status) != self.negated
+    }
+
+    pub fn describe(&self) -> String {
+        format!("[{}{}={}]", if self.negated { "!" } else { "" }, self.status.as_str(), self.action.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NssSource {
+    pub name: String,
+    pub criteria: Vec<NssCriterion>,
+}
+
+impl NssSource {
+    pub fn new(name: &str) -> Self {
+        NssSource {
+            name: name.to_string(),
+            criteria: Vec::new(),
+        }
+    }
+
+    /// Action taken after this source reports `status`, together with the
+    /// explicit criterion that selected it. `None` means the glibc default
+    /// applied (return on SUCCESS, continue otherwise).
+    pub fn action_for(&self, status: NssStatus) -> (NssAction, Option<&NssCriterion>) {
+        // Later criteria override earlier ones, as in glibc
+        if let Some(criterion) = self.criteria.iter().rev().find(|c| c.applies_to(status)) {
+            return (criterion.action, Some(criterion));
+        }
+        let action = if status == NssStatus::Success { NssAction::Return } else { NssAction::Continue };
+        (action, None)
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct NssOrder {
-    pub hosts: Vec<String>,
+    pub hosts: Vec<NssSource>,
     #[allow(dead_code)]
-    pub passwd: Vec<String>,
+    pub passwd: Vec<NssSource>,
     #[allow(dead_code)]
-    pub group: Vec<String>,
+    pub group: Vec<NssSource>,
+}
+
+fn parse_criteria(spec: &str) -> Vec<NssCriterion> {
+    spec.split_whitespace()
+        .filter_map(|item| {
+            let (status, action) = item.split_once('=')?;
+            let (negated, status) = match status.strip_prefix('!') {
+                Some(status) => (true, status),
+                None => (false, status),
+            };
+            Some(NssCriterion {
+                status: NssStatus::parse(status)?,
+                negated,
+                action: NssAction::parse(action)?,
+            })
+        })
+        .collect()
 }
 
-pub fn parse_nsswitch_file<P: AsRef<Path>>(path: P) -> io::Result<NssOrder> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
+pub fn parse_sources(value: &str) -> Vec<NssSource> {
+    let mut sources: Vec<NssSource> = Vec::new();
+    let mut rest = value.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (spec, remaining) = after.split_once(']').unwrap_or((after, ""));
+            if let Some(source) = sources.last_mut() {
+                source.criteria.extend(parse_criteria(spec));
+            }
+            rest = remaining.trim_start();
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '[')
+                .unwrap_or(rest.len());
+            sources.push(NssSource::new(&rest[..end]));
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    sources
+}
+
+pub fn parse_nsswitch<R: BufRead>(reader: R) -> io::Result<NssOrder> {
     let mut map = HashMap::new();
 
     for line in reader.lines() {
@@ -28,12 +177,7 @@ pub fn parse_nsswitch_file<P: AsRef<Path>>(path: P) -> io::Result<NssOrder> {
 
         if let Some((key, value)) = line.split_once(':') {
             let key = key.trim();
-            let value = value.trim();
-            let sources: Vec<String> = value
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect();
-            map.insert(key.to_string(), sources);
+            map.insert(key.to_string(), parse_sources(value));
         }
     }
 
@@ -42,4 +186,4 @@ pub fn parse_nsswitch_file<P: AsRef<Path>>(path: P) -> io::Result<NssOrder> {
         passwd: map.get("passwd").cloned().unwrap_or_default(),
         group: map.get("group").cloned().unwrap_or_default(),
     })
-}
\ No newline at end of file
+}
@@ -5,25 +5,44 @@ use std::path::Path;
 #[derive(Debug, Clone)]
 pub struct UserEntry {
     pub name: String,
+    pub password: String,
     pub uid: u32,
-    #[allow(dead_code)]
     pub gid: u32,
-    #[allow(dead_code)]
     pub gecos: String,
-    #[allow(dead_code)]
     pub home: String,
-    #[allow(dead_code)]
     pub shell: String,
     #[allow(dead_code)]
     pub source: String,
+    pub line: usize,
+}
+
+impl UserEntry {
+    /// The entry as `getent passwd` prints it.
+    pub fn getent_line(&self) -> String {
+        format!("{}:{}:{}:{}:{}:{}:{}", self.name, self.password, self.uid, self.gid, self.gecos, self.home, self.shell)
+    }
+}
+
+/// A uid or gid field as glibc's files module reads it: an empty field is 0,
+/// and anything other than digits makes it ignore the whole line.
+pub fn parse_id(field: &str) -> Option<u32> {
+    if field.is_empty() {
+        return Some(0);
+    }
+    if !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
 }
 
 pub fn parse_passwd_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<UserEntry>> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
+    parse_passwd(io::BufReader::new(fs::File::open(path)?))
+}
+
+pub fn parse_passwd<R: BufRead>(reader: R) -> io::Result<Vec<UserEntry>> {
     let mut entries = Vec::new();
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line?;
         let line = line.trim();
 
@@ -37,21 +56,27 @@ pub fn parse_passwd_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<UserEntry>>
             continue;
         }
 
+        // glibc ignores lines whose ids are not numbers
+        let (Some(uid), Some(gid)) = (parse_id(parts[2]), parse_id(parts[3])) else {
+            continue;
+        };
+
         let name = parts[0].to_string();
-        let uid = parts[2].parse().unwrap_or(0);
-        let gid = parts[3].parse().unwrap_or(0);
+        let password = parts[1].to_string();
         let gecos = parts[4].to_string();
         let home = parts[5].to_string();
         let shell = parts[6].to_string();
 
         entries.push(UserEntry {
             name,
+            password,
             uid,
             gid,
             gecos,
             home,
             shell,
             source: "/etc/passwd".to_string(),
+            line: index + 1,
         });
     }
 
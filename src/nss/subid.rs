@@ -0,0 +1,85 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// A delegated range from /etc/subuid or /etc/subgid.
+#[derive(Debug, Clone)]
+pub struct SubIdRange {
+    pub owner: String,
+    pub start: u32,
+    pub count: u32,
+    pub source: String,
+    pub line: usize,
+}
+
+impl SubIdRange {
+    pub fn contains(&self, id: u32) -> bool {
+        id >= self.start && ((id - self.start) as u64) < self.count as u64
+    }
+}
+
+pub fn parse_subid_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<SubIdRange>> {
+    let source = path.as_ref().display().to_string();
+    let file = fs::File::open(path)?;
+    let reader = io::BufReader::new(file);
+    let mut entries = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split(':').collect();
+        if parts.len() < 3 {
+            continue;
+        }
+        let (Ok(start), Ok(count)) = (parts[1].parse(), parts[2].parse()) else {
+            continue;
+        };
+
+        entries.push(SubIdRange {
+            owner: parts[0].to_string(),
+            start,
+            count,
+            source: source.clone(),
+            line: index + 1,
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn find_subid_owner(id: u32, ranges: &[SubIdRange]) -> Option<&SubIdRange> {
+    ranges.iter().find(|range| range.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges() -> Vec<SubIdRange> {
+        parse_subid_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/root/etc/subuid")).unwrap()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let ranges = ranges();
+        assert_eq!(ranges.iter().map(|r| (r.owner.as_str(), r.start, r.count, r.line)).collect::<Vec<_>>(), [("deploy", 100000, 65536, 2), ("ci", 165536, 65536, 3)]);
+    }
+
+    #[test]
+    fn owners_by_range() {
+        let ranges = ranges();
+        let owner = |id| find_subid_owner(id, &ranges).map(|range| range.owner.as_str());
+        assert_eq!(owner(100000), Some("deploy"));
+        assert_eq!(owner(100000 + 65536 - 1), Some("deploy"));
+        assert_eq!(owner(165536), Some("ci"));
+        assert_eq!(owner(165536 + 65536 - 1), Some("ci"));
+        assert_eq!(owner(99999), None);
+        assert_eq!(owner(165536 + 65536), None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::namespaces::get_proc_root_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+}
+
+/// A file the runtime bind-mounted over a path inside the container.
+#[derive(Debug, Clone)]
+pub struct InjectedFile {
+    pub target: String,
+    pub host_source: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerInfo {
+    pub runtime: ContainerRuntime,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub evidence: Vec<String>,
+    pub injected: Vec<InjectedFile>,
+}
+
+impl ContainerInfo {
+    pub fn label(&self) -> String {
+        match (&self.name, &self.id) {
+            (Some(name), _) => name.clone(),
+            (None, Some(id)) => id.chars().take(12).collect(),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    pub fn injected_file(&self, target: &str) -> Option<&InjectedFile> {
+        self.injected.iter().find(|file| file.target == target)
+    }
+}
+
+const INJECTED_TARGETS: [&str; 3] = ["/etc/hosts", "/etc/resolv.conf", "/etc/hostname"];
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Finds a 64-hex container id following `marker` in a cgroup or mount path.
+fn id_after<'a>(path: &'a str, marker: &str) -> Option<&'a str> {
+    let start = path.find(marker)? + marker.len();
+    let candidate = path.get(start..start + 64)?;
+    is_container_id(candidate).then_some(candidate)
+}
+
+fn runtime_from_cgroup(line: &str) -> Option<(ContainerRuntime, String)> {
+    let path = line.splitn(3, ':').nth(2)?;
+    for marker in ["docker-", "/docker/"] {
+        if let Some(id) = id_after(path, marker) {
+            return Some((ContainerRuntime::Docker, id.to_string()));
+        }
+    }
+    id_after(path, "libpod-").map(|id| (ContainerRuntime::Podman, id.to_string()))
+}
+
+/// Podman's `overlay-containers/<id>/userdata` also ends in `containers/<id>`,
+/// so it is recognized first.
+fn runtime_from_mount_source(source: &str) -> Option<(ContainerRuntime, String)> {
+    if let Some(id) = id_after(source, "overlay-containers/") {
+        return Some((ContainerRuntime::Podman, id.to_string()));
+    }
+    id_after(source, "/containers/").map(|id| (ContainerRuntime::Docker, id.to_string()))
+}
+
+/// Reads `/run/.containerenv`, which podman fills with key="value" lines.
+fn parse_containerenv(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+fn docker_container_name(id: &str) -> Option<String> {
+    let config = fs::read_to_string(format!("/var/lib/docker/containers/{}/config.v2.json", id)).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&config).ok()?;
+    Some(value.get("Name")?.as_str()?.trim_start_matches('/').to_string())
+}
+
+/// Detects whether `pid` runs inside a docker or podman container, using the
+/// same traces the runtimes leave behind: the cgroup path, the marker files in
+/// the container root and the bind mounts over /etc/hosts and /etc/resolv.conf.
+pub fn detect_container(pid: u32) -> Option<ContainerInfo> {
+    let mut runtime = None;
+    let mut id: Option<String> = None;
+    let mut name = None;
+    let mut evidence = Vec::new();
+    let mut injected = Vec::new();
+
+    if let Ok(cgroup) = fs::read_to_string(format!("/proc/{}/cgroup", pid)) {
+        if let Some((found, found_id)) = cgroup.lines().find_map(runtime_from_cgroup) {
+            evidence.push(format!("cgroup path names {} container {}", found.as_str(), &found_id[..12]));
+            runtime = Some(found);
+            id = Some(found_id);
+        }
+    }
+
+    if Path::new(&get_proc_root_path(pid, "/.dockerenv")).exists() {
+        evidence.push("/.dockerenv exists".to_string());
+        runtime.get_or_insert(ContainerRuntime::Docker);
+    }
+
+    if let Ok(content) = fs::read_to_string(get_proc_root_path(pid, "/run/.containerenv")) {
+        evidence.push("/run/.containerenv exists".to_string());
+        runtime.get_or_insert(ContainerRuntime::Podman);
+        for (key, value) in parse_containerenv(&content) {
+            match key.as_str() {
+                "name" if !value.is_empty() => name = Some(value),
+                "id" if is_container_id(&value) => {
+                    id.get_or_insert(value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // mountinfo: "<id> <parent> <dev> <root> <mount point> ..."
+    if let Ok(mountinfo) = fs::read_to_string(format!("/proc/{}/mountinfo", pid)) {
+        for line in mountinfo.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 || !INJECTED_TARGETS.contains(&fields[4]) {
+                continue;
+            }
+            let (root, target) = (fields[3], fields[4]);
+            if let Some((found, found_id)) = runtime_from_mount_source(root) {
+                runtime.get_or_insert(found);
+                id.get_or_insert(found_id);
+                injected.push(InjectedFile {
+                    target: target.to_string(),
+                    host_source: root.to_string(),
+                });
+            }
+        }
+        if !injected.is_empty() {
+            let targets: Vec<&str> = injected.iter().map(|file| file.target.as_str()).collect();
+            evidence.push(format!("bind mounts over {}", targets.join(", ")));
+        }
+    }
+
+    let runtime = runtime?;
+    if name.is_none() && runtime == ContainerRuntime::Docker {
+        name = id.as_deref().and_then(docker_container_name);
+    }
+
+    Some(ContainerInfo {
+        runtime,
+        id,
+        name,
+        evidence,
+        injected,
+    })
+}
+
+/// Explains the resolver a container's resolv.conf points at: docker's
+/// embedded DNS server or the aardvark-dns instance netavark runs per network.
+pub fn explain_container_dns(info: &ContainerInfo, nameservers: &[String]) -> Option<String> {
+    if nameservers.iter().any(|ns| ns == "127.0.0.11") {
+        return Some(
+            "resolv.conf points at 127.0.0.11, docker's embedded DNS server: it answers container names and \
+             aliases on user-defined networks and forwards everything else to the host's resolvers"
+                .to_string(),
+        );
+    }
+    if info.runtime != ContainerRuntime::Podman {
+        return None;
+    }
+
+    for dir in aardvark_config_dirs() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(content) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let mut lines = content.lines();
+            // First line: listen addresses of aardvark-dns, then optional upstreams
+            let listen: Vec<&str> = lines
+                .next()
+                .and_then(|line| line.split_whitespace().next())
+                .map(|ips| ips.split(',').collect())
+                .unwrap_or_default();
+            if !nameservers.iter().any(|ns| listen.contains(&ns.as_str())) {
+                continue;
+            }
+            let network = entry.file_name().to_string_lossy().to_string();
+            let names = info.id.as_deref().and_then(|id| {
+                lines
+                    .filter_map(|line| {
+                        let fields: Vec<&str> = line.split_whitespace().collect();
+                        (fields.first()? == &id).then(|| fields.get(3).copied().unwrap_or_default().to_string())
+                    })
+                    .next()
+            });
+            let mut explanation = format!(
+                "resolv.conf points at aardvark-dns ({}) for podman network {}, configured in {}",
+                listen.join(", "),
+                network,
+                entry.path().display()
+            );
+            if let Some(names) = names {
+                explanation.push_str(&format!("; this container is published as {}", names));
+            }
+            return Some(explanation);
+        }
+    }
+    None
+}
+
+fn aardvark_config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/run/containers/networks/aardvark-dns")];
+    if let Ok(entries) = fs::read_dir("/run/user") {
+        for entry in entries.flatten() {
+            dirs.push(entry.path().join("containers/networks/aardvark-dns"));
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn mount_sources_name_the_runtime() {
+        for docker in [format!("/var/lib/docker/containers/{}/hosts", ID), format!("/containers/{}/resolv.conf", ID)] {
+            assert_eq!(runtime_from_mount_source(&docker), Some((ContainerRuntime::Docker, ID.to_string())), "{}", docker);
+        }
+
+        // /var/lib/containers mounted on its own: the root starts below it
+        for podman in [
+            format!("/storage/overlay-containers/{}/userdata/hosts", ID),
+            format!("/var/lib/containers/storage/overlay-containers/{}/userdata/resolv.conf", ID),
+        ] {
+            assert_eq!(runtime_from_mount_source(&podman), Some((ContainerRuntime::Podman, ID.to_string())), "{}", podman);
+        }
+        assert_eq!(runtime_from_mount_source("/etc/hosts"), None);
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+
+use crate::context::ResolveContext;
+use crate::dns::resolv_conf::{self, ResolvConf};
+
+const KUBELET_HOSTS_HEADER: &str = "# Kubernetes-managed hosts file";
+const HOST_ALIASES_MARKER: &str = "# Entries added by HostAliases.";
+pub const SERVICE_ACCOUNT_NAMESPACE: &str = "/var/run/secrets/kubernetes.io/serviceaccount/namespace";
+
+/// kubelet's KubeletConfiguration on kubeadm nodes.
+const KUBELET_CONFIG: &str = "/var/lib/kubelet/config.yaml";
+
+/// What kubelet hands dnsPolicy Default pods when `resolvConf` is not set:
+/// systemd-resolved's upstream list on resolved nodes, else /etc/resolv.conf.
+const NODE_RESOLV_CONFS: [&str; 2] = ["/run/systemd/resolve/resolv.conf", "/etc/resolv.conf"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsPolicy {
+    ClusterFirst,
+    Default,
+    None,
+}
+
+impl DnsPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DnsPolicy::ClusterFirst => "ClusterFirst",
+            DnsPolicy::Default => "Default",
+            DnsPolicy::None => "None",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HostAlias {
+    pub ip: String,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PodInfo {
+    pub name: Option<String>,
+    pub namespace: Option<String>,
+    pub cluster_domain: Option<String>,
+    pub dns_policy: DnsPolicy,
+    pub cluster_dns: Vec<String>,
+    pub host_aliases: Vec<HostAlias>,
+    pub kubelet_hosts: bool,
+    pub evidence: Vec<String>,
+}
+
+impl PodInfo {
+    pub fn summary(&self) -> String {
+        format!(
+            "pod {} in namespace {}, cluster domain {}, dnsPolicy {}",
+            self.name.as_deref().unwrap_or("(unknown)"),
+            self.namespace.as_deref().unwrap_or("(unknown)"),
+            self.cluster_domain.as_deref().unwrap_or("(unknown)"),
+            self.dns_policy.as_str()
+        )
+    }
+
+    pub fn host_alias(&self, name: &str) -> Option<&HostAlias> {
+        self.host_aliases.iter().find(|alias| alias.names.iter().any(|n| n == name))
+    }
+
+    /// What a fully qualified candidate means inside the cluster.
+    pub fn interpret_name(&self, candidate: &str) -> String {
+        let Some(domain) = &self.cluster_domain else {
+            return "outside the cluster domain".to_string();
+        };
+        let Some(rest) = candidate.strip_suffix(&format!(".{}", domain)) else {
+            return "outside the cluster domain, forwarded upstream by cluster DNS".to_string();
+        };
+        if let Some(rest) = rest.strip_suffix(".svc") {
+            let labels: Vec<&str> = rest.split('.').collect();
+            return match labels.as_slice() {
+                [namespace] => format!("namespace-level name for {}, no Service record", namespace),
+                [service, namespace] => format!("Service {} in namespace {}", service, namespace),
+                [host, service, namespace] => {
+                    format!("pod {} behind headless Service {} in namespace {}", host, service, namespace)
+                }
+                _ => "not a valid Service name".to_string(),
+            };
+        }
+        if let Some(rest) = rest.strip_suffix(".pod") {
+            return format!("pod A record {}", rest);
+        }
+        "inside the cluster domain but not a Service or pod name".to_string()
+    }
+}
+
+/// Derives namespace and cluster domain from a kubelet-generated search list
+/// (`<ns>.svc.<domain> svc.<domain> <domain>`).
+fn cluster_search(conf: &ResolvConf) -> Option<(String, String)> {
+    let search = conf.search_list();
+    search.iter().find_map(|first| {
+        let (namespace, domain) = first.split_once(".svc.")?;
+        search
+            .iter()
+            .any(|d| d == &format!("svc.{}", domain))
+            .then(|| (namespace.to_string(), domain.to_string()))
+    })
+}
+
+fn parse_host_aliases(content: &str) -> Vec<HostAlias> {
+    let Some((_, section)) = content.split_once(HOST_ALIASES_MARKER) else {
+        return Vec::new();
+    };
+    section
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let ip = parts.next()?.to_string();
+            let names: Vec<String> = parts.map(|s| s.to_string()).collect();
+            (!names.is_empty()).then_some(HostAlias { ip, names })
+        })
+        .collect()
+}
+
+/// The `resolvConf` setting of a KubeletConfiguration.
+fn kubelet_resolv_conf(config: &str) -> Option<String> {
+    let config: serde_yaml::Value = serde_yaml::from_str(config).ok()?;
+    Some(config.get("resolvConf")?.as_str()?.to_string()).filter(|path| !path.is_empty())
+}
+
+/// The node resolv.conf files a dnsPolicy Default pod may have been given:
+/// the one kubelet is configured with, or else the ones it picks by default.
+fn node_resolv_confs() -> Vec<ResolvConf> {
+    let paths = match fs::read_to_string(KUBELET_CONFIG).ok().as_deref().and_then(kubelet_resolv_conf) {
+        Some(path) => vec![path],
+        None => NODE_RESOLV_CONFS.iter().map(|path| path.to_string()).collect(),
+    };
+    paths.iter().filter_map(|path| resolv_conf::parse_resolv_conf_file(path).ok()).collect()
+}
+
+/// Whether a pod's resolv.conf is a copy of a node's.
+fn inherited_from(conf: &ResolvConf, nodes: &[ResolvConf]) -> bool {
+    nodes.iter().any(|node| node.nameservers == conf.nameservers && node.search_list() == conf.search_list())
+}
+
+/// Recognizes a kubelet-managed pod from the files kubelet and the container
+/// runtime place in its root: the managed /etc/hosts, the cluster search list
+/// in /etc/resolv.conf and the service account namespace.
+pub fn detect_pod(ctx: &ResolveContext) -> Option<PodInfo> {
+    let mut evidence = Vec::new();
+
+    let hosts = ctx.read_to_string("/etc/hosts").unwrap_or_default();
+    let kubelet_hosts = hosts.starts_with(KUBELET_HOSTS_HEADER);
+    if kubelet_hosts {
+        evidence.push("/etc/hosts is kubelet-managed".to_string());
+    }
+
+    let conf = ctx.open("/etc/resolv.conf").and_then(resolv_conf::parse_resolv_conf).ok();
+    let search = conf.as_ref().and_then(cluster_search);
+    if search.is_some() {
+        evidence.push("resolv.conf has the cluster search list".to_string());
+    }
+
+    let mut namespace = ctx.read_to_string(SERVICE_ACCOUNT_NAMESPACE)
+        .ok()
+        .map(|ns| ns.trim().to_string())
+        .filter(|ns| !ns.is_empty());
+    if namespace.is_some() {
+        evidence.push("service account token is mounted".to_string());
+    }
+
+    if let Some(pid) = ctx.pid {
+        let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid)).unwrap_or_default();
+        if cgroup.contains("kubepods") {
+            evidence.push("cgroup is under kubepods".to_string());
+        }
+    }
+
+    if evidence.is_empty() {
+        return None;
+    }
+
+    let (dns_policy, cluster_domain, cluster_dns) = match (&search, &conf) {
+        (Some((search_namespace, domain)), Some(conf)) => {
+            namespace.get_or_insert_with(|| search_namespace.clone());
+            (DnsPolicy::ClusterFirst, Some(domain.clone()), conf.nameservers.clone())
+        }
+        // Without the cluster search list the pod either inherited the node's
+        // resolv.conf (Default) or got a custom dnsConfig (None).
+        (None, Some(conf)) => {
+            let inherited = ctx.pid.is_some() && inherited_from(conf, &node_resolv_confs());
+            let policy = if inherited { DnsPolicy::Default } else { DnsPolicy::None };
+            (policy, None, Vec::new())
+        }
+        (_, None) => (DnsPolicy::None, None, Vec::new()),
+    };
+
+    let name = ctx.read_to_string("/etc/hostname")
+        .ok()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty());
+
+    Some(PodInfo {
+        name,
+        namespace,
+        cluster_domain,
+        dns_policy,
+        cluster_dns,
+        host_aliases: parse_host_aliases(&hosts),
+        kubelet_hosts,
+        evidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> ResolvConf {
+        resolv_conf::parse_resolv_conf(content.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn kubelet_resolv_conf_setting() {
+        let config = "apiVersion: kubelet.config.k8s.io/v1beta1\nkind: KubeletConfiguration\nresolvConf: /run/systemd/resolve/resolv.conf\n";
+        assert_eq!(kubelet_resolv_conf(config).as_deref(), Some("/run/systemd/resolve/resolv.conf"));
+        assert_eq!(kubelet_resolv_conf("kind: KubeletConfiguration\nresolvConf: \"\"\n"), None);
+        assert_eq!(kubelet_resolv_conf("kind: KubeletConfiguration\n"), None);
+    }
+
+    #[test]
+    fn default_pods_match_the_resolved_upstream_list() {
+        let stub = parse("nameserver 127.0.0.53\noptions edns0 trust-ad\nsearch corp\n");
+        let upstream = parse("nameserver 10.0.0.1\nnameserver 10.0.0.2\nsearch corp\n");
+        let pod = parse("nameserver 10.0.0.1\nnameserver 10.0.0.2\nsearch corp\n");
+        assert!(!inherited_from(&pod, std::slice::from_ref(&stub)));
+        assert!(inherited_from(&pod, &[upstream, stub]));
+    }
+}
@@ -1 +1,4 @@
-pub mod namespaces;
\ No newline at end of file
+pub mod container;
+pub mod kubernetes;
+pub mod namespaces;
+pub mod userns;
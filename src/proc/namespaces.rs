@@ -1,4 +1,10 @@
+use std::collections::BTreeMap;
 use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+pub const NETNS_RUN_DIR: &str = "/run/netns";
+pub const NETNS_ETC_DIR: &str = "/etc/netns";
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -7,6 +13,34 @@ pub struct NamespaceInfo {
     pub net_ns: String,
 }
 
+impl NamespaceInfo {
+    /// The inode number in a link such as `net:[4026531840]`.
+    pub fn net_inode(&self) -> Option<u64> {
+        namespace_inode(&self.net_ns)
+    }
+}
+
+fn namespace_inode(link: &str) -> Option<u64> {
+    link.split_once('[')?.1.strip_suffix(']')?.parse().ok()
+}
+
+/// A file from /etc/netns/<name>/ that `ip netns exec` bind-mounts over /etc.
+#[derive(Debug, Clone)]
+pub struct NetnsOverride {
+    pub target: String,
+    pub source: String,
+    /// Whether the bind mount is actually in place for the process being
+    /// inspected. Processes that entered the namespace without `ip netns exec`
+    /// (e.g. via nsenter) see the plain /etc files.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NamedNetns {
+    pub name: String,
+    pub overrides: Vec<NetnsOverride>,
+}
+
 #[allow(dead_code)]
 pub fn get_namespace_info(pid: u32) -> std::io::Result<NamespaceInfo> {
     let mnt_path = format!("/proc/{}/ns/mnt", pid);
@@ -32,4 +66,79 @@ pub fn namespaces_differ(pid: u32) -> std::io::Result<bool> {
 #[allow(dead_code)]
 pub fn get_proc_root_path(pid: u32, relative_path: &str) -> String {
     format!("/proc/{}/root{}", pid, relative_path)
-}
\ No newline at end of file
+}
+
+/// Finds the `ip netns` name whose /run/netns handle refers to `inode`.
+pub fn netns_name_for_inode(inode: u64) -> Option<String> {
+    let entries = fs::read_dir(NETNS_RUN_DIR).ok()?;
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| fs::metadata(entry.path()).map(|m| m.ino() == inode).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names.into_iter().next()
+}
+
+/// The names `ip netns list` shows: the handles under /run/netns, sorted.
+pub fn netns_names() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(NETNS_RUN_DIR)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().to_string()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Lists the /etc/netns/<name> overlay. With a pid, checks the process's
+/// mountinfo to see which overrides are really mounted; without one, every
+/// override is assumed applied, as for a fresh `ip netns exec`.
+pub fn named_netns(name: &str, pid: Option<u32>) -> NamedNetns {
+    let dir = Path::new(NETNS_ETC_DIR).join(name);
+    let mountinfo = pid.and_then(|pid| fs::read_to_string(format!("/proc/{}/mountinfo", pid)).ok());
+
+    let mut overrides: Vec<NetnsOverride> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| {
+                    let file = entry.file_name().to_string_lossy().to_string();
+                    let source = dir.join(&file).display().to_string();
+                    let target = format!("/etc/{}", file);
+                    let applied = match &mountinfo {
+                        Some(mountinfo) => mountinfo.lines().any(|line| {
+                            let fields: Vec<&str> = line.split_whitespace().collect();
+                            fields.len() > 4 && fields[4] == target && fields[3].ends_with(&format!("netns/{}/{}", name, file))
+                        }),
+                        None => true,
+                    };
+                    NetnsOverride { target, source, applied }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    overrides.sort_by(|a, b| a.target.cmp(&b.target));
+
+    NamedNetns {
+        name: name.to_string(),
+        overrides,
+    }
+}
+
+/// Detects whether `pid` lives in a named network namespace.
+pub fn named_netns_for_pid(pid: u32) -> Option<NamedNetns> {
+    let inode = get_namespace_info(pid).ok()?.net_inode()?;
+    let name = netns_name_for_inode(inode)?;
+    Some(named_netns(&name, Some(pid)))
+}
+
+/// The namespace links of `pid` (`net:[4026531840]` and so on), keyed by
+/// namespace type.
+pub fn namespace_ids(pid: u32) -> BTreeMap<String, String> {
+    ["cgroup", "ipc", "mnt", "net", "pid", "user", "uts"]
+        .iter()
+        .filter_map(|kind| {
+            let link = fs::read_link(format!("/proc/{}/ns/{}", pid, kind)).ok()?;
+            Some((kind.to_string(), link.to_string_lossy().to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// One line of /proc/<pid>/uid_map or gid_map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdMapRange {
+    pub inside: u32,
+    pub outside: u32,
+    pub count: u32,
+}
+
+impl IdMapRange {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}-{} → {}-{}",
+            self.inside,
+            self.inside as u64 + self.count as u64 - 1,
+            self.outside,
+            self.outside as u64 + self.count as u64 - 1
+        )
+    }
+}
+
+pub fn parse_id_map_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<IdMapRange>> {
+    let file = fs::File::open(path)?;
+    let reader = io::BufReader::new(file);
+    let mut ranges = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            continue;
+        }
+        let (Ok(inside), Ok(outside), Ok(count)) = (parts[0].parse(), parts[1].parse(), parts[2].parse()) else {
+            continue;
+        };
+        ranges.push(IdMapRange { inside, outside, count });
+    }
+
+    Ok(ranges)
+}
+
+/// Maps an id inside the namespace to the id it has on the host.
+pub fn to_host_id(ranges: &[IdMapRange], id: u32) -> Option<(u32, &IdMapRange)> {
+    ranges.iter().find_map(|range| {
+        let offset = id.checked_sub(range.inside)?;
+        (offset < range.count).then(|| (range.outside + offset, range))
+    })
+}
+
+/// True for the initial namespace's map (`0 0 4294967295`), where inside
+/// and host ids are the same.
+pub fn is_identity(ranges: &[IdMapRange]) -> bool {
+    ranges.iter().all(|range| range.inside == range.outside)
+}
+
+#[derive(Debug, Clone)]
+pub struct UserNamespace {
+    pub uid_map: Vec<IdMapRange>,
+    pub gid_map: Vec<IdMapRange>,
+}
+
+/// Reads the id maps of `pid`, or `None` when it shares the host's ids.
+pub fn user_namespace(pid: u32) -> Option<UserNamespace> {
+    let uid_map = parse_id_map_file(format!("/proc/{}/uid_map", pid)).ok()?;
+    let gid_map = parse_id_map_file(format!("/proc/{}/gid_map", pid)).ok()?;
+    if is_identity(&uid_map) && is_identity(&gid_map) {
+        return None;
+    }
+    Some(UserNamespace { uid_map, gid_map })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: [IdMapRange; 2] = [
+        IdMapRange { inside: 0, outside: 100000, count: 1000 },
+        IdMapRange { inside: 1000, outside: 1000, count: 1 },
+    ];
+
+    #[test]
+    fn ids_map_through_their_range() {
+        assert_eq!(to_host_id(&MAP, 0), Some((100000, &MAP[0])));
+        assert_eq!(to_host_id(&MAP, 42), Some((100042, &MAP[0])));
+        // inside + count - 1 is the last mapped id
+        assert_eq!(to_host_id(&MAP, 999), Some((100999, &MAP[0])));
+        assert_eq!(to_host_id(&MAP, 1000), Some((1000, &MAP[1])));
+    }
+
+    #[test]
+    fn unmapped_ids() {
+        assert_eq!(to_host_id(&MAP, 1001), None);
+        assert_eq!(to_host_id(&MAP, u32::MAX), None);
+        assert_eq!(to_host_id(&[], 0), None);
+    }
+
+    #[test]
+    fn identity_maps() {
+        assert!(is_identity(&[IdMapRange { inside: 0, outside: 0, count: u32::MAX }]));
+        assert!(!is_identity(&MAP));
+    }
+}
@@ -0,0 +1,50 @@
+use std::io::Write;
+use std::net::IpAddr;
+
+use super::output::{Database, Output, Record, ResolutionResult};
+use super::renderer::{unsupported, Renderer};
+
+/// Lines as `getent hosts|passwd|group` prints them, so scripts that parse
+/// getent can switch over. Nothing is printed when the name does not
+/// resolve; the caller exits with status 2 as getent does.
+pub struct Getent;
+
+/// What getent prints for one lookup, one line per host address; empty when
+/// getent would exit with status 2.
+pub fn getent_lines(database: Database, result: &ResolutionResult) -> anyhow::Result<Vec<String>> {
+    let Some(answer) = &result.result else {
+        return Ok(Vec::new());
+    };
+    match (database, &result.record) {
+        (Database::Hosts, record) => {
+            let names = match record {
+                Some(Record::Host(entry)) => entry.names.join(" "),
+                // An address key is reverse-resolved; one nothing names is not found
+                _ if result.name.parse::<IpAddr>().is_ok() => return Ok(Vec::new()),
+                _ => result.name.clone(),
+            };
+            let addresses = if result.addresses.is_empty() { std::slice::from_ref(answer) } else { result.addresses.as_slice() };
+            // getent asks for AF_INET6 first and falls back to AF_INET
+            let has_v6 = addresses.iter().any(|address| address.contains(':'));
+            Ok(addresses
+                .iter()
+                .filter(|address| address.contains(':') == has_v6)
+                .map(|address| format!("{:<15} {}", address, names))
+                .collect())
+        }
+        (_, Some(record)) => Ok(vec![record.getent_line()]),
+        (database, None) => anyhow::bail!("the source that answered {} does not provide a full {} entry", result.name, database.as_str()),
+    }
+}
+
+impl Renderer for Getent {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let Output::Lookup { database, result, .. } = output else {
+            return Err(unsupported("getent", output));
+        };
+        for line in getent_lines(*database, result)? {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
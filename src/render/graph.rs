@@ -0,0 +1,25 @@
+use std::io::Write;
+
+use super::output::Output;
+use super::renderer::{unsupported, Renderer};
+use crate::explain::graph;
+
+/// The resolution tree as a Graphviz or Mermaid graph.
+pub struct Graph {
+    pub mermaid: bool,
+}
+
+impl Renderer for Graph {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let Output::Lookup { result, .. } = output else {
+            return Err(unsupported(if self.mermaid { "mermaid" } else { "dot" }, output));
+        };
+        let rendered = if self.mermaid {
+            graph::to_mermaid(&result.name, result.result.as_deref(), &result.tree)
+        } else {
+            graph::to_dot(&result.name, result.result.as_deref(), &result.tree)
+        };
+        write!(out, "{}", rendered)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::Write;
+
+use super::output::{Database, Output};
+use super::renderer::Renderer;
+use crate::explain::decision_tree::DecisionNode;
+
+/// Keeps table cells on one row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn tree_rows(out: &mut dyn Write, nodes: &[DecisionNode], prefix: &str) -> std::io::Result<()> {
+    for (i, node) in nodes.iter().enumerate() {
+        let number = format!("{}{}", prefix, i + 1);
+        let source = if node.taken { cell(&node.source) } else { format!("~~{}~~ (not taken)", cell(&node.source)) };
+        let status = match (&node.status, &node.action, &node.criterion) {
+            (Some(status), Some(action), Some(criterion)) => format!("{} → {} `{}`", status.as_str(), action.as_str(), criterion),
+            (Some(status), Some(action), None) => format!("{} → {}", status.as_str(), action.as_str()),
+            (Some(status), None, _) => status.as_str().to_string(),
+            _ => String::new(),
+        };
+        let mut reason = cell(&node.reason);
+        if let Some(condition) = &node.condition {
+            let label = if node.taken { "Otherwise" } else { "Condition" };
+            if !reason.is_empty() {
+                reason.push_str("<br>");
+            }
+            reason.push_str(&format!("*{}: {}*", label, cell(condition)));
+        }
+        writeln!(out, "| {} | {} | {} | {} | {} |", number, source, cell(&node.outcome.describe()), status, reason)?;
+        tree_rows(out, &node.children, &format!("{}.", number))?;
+    }
+    Ok(())
+}
+
+/// Markdown for wiki pages and tickets. Lookups always include the
+/// resolution path as a table, since that is what a page is written for.
+pub struct Markdown;
+
+impl Renderer for Markdown {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        match output {
+            Output::Lookup { database, result, .. } => {
+                let kind = match database {
+                    Database::Hosts => "host",
+                    Database::Passwd => "user",
+                    Database::Group => "group",
+                };
+                writeln!(out, "### {} `{}`\n", kind, result.name)?;
+                match (&result.result, &result.host_id) {
+                    (Some(answer), Some(host_id)) => writeln!(out, "**Answer:** `{}` (host id `{}`)\n", answer, host_id)?,
+                    (Some(answer), None) => writeln!(out, "**Answer:** `{}`\n", answer)?,
+                    (None, _) => writeln!(out, "**Answer:** none\n")?,
+                }
+                writeln!(out, "| # | Source | Outcome | Status | Reason |")?;
+                writeln!(out, "|---|---|---|---|---|")?;
+                tree_rows(out, &result.tree.nodes, "")?;
+                if let Some(shadowed) = result.shadowed.as_ref().filter(|shadowed| !shadowed.conflicts.is_empty()) {
+                    writeln!(out, "\n**Conflicts:**\n")?;
+                    for conflict in &shadowed.conflicts {
+                        match &conflict.winner {
+                            Some(winner) => writeln!(out, "- {} answers `{}`, but the lookup returned `{}`", conflict.source, conflict.answer, winner)?,
+                            None => writeln!(out, "- {} answers `{}`, but the lookup returned nothing", conflict.source, conflict.answer)?,
+                        }
+                    }
+                }
+                if let Some(why_not) = &result.why_not {
+                    writeln!(out, "\n**Why not `{}`:**\n", why_not.expected)?;
+                    for line in &why_not.explanation {
+                        writeln!(out, "- {}", line)?;
+                    }
+                }
+            }
+            Output::Audit { suite, report, .. } => {
+                writeln!(out, "### Audit: {}\n", suite)?;
+                if report.findings.is_empty() {
+                    writeln!(out, "No findings.")?;
+                } else {
+                    writeln!(out, "| Severity | Location | Rule | Message | Fix |")?;
+                    writeln!(out, "|---|---|---|---|---|")?;
+                    for finding in &report.findings {
+                        writeln!(
+                            out,
+                            "| {} | `{}` | `{}` | {} | {} |",
+                            finding.severity.as_str(),
+                            finding.location(),
+                            finding.rule,
+                            cell(&finding.message),
+                            cell(finding.remediation.as_deref().unwrap_or_default())
+                        )?;
+                    }
+                }
+                if !report.notes.is_empty() {
+                    writeln!(out, "\n**Notes:**\n")?;
+                    for note in &report.notes {
+                        writeln!(out, "- {}", note)?;
+                    }
+                }
+            }
+            Output::Check { report, .. } => {
+                let passed = report.results.iter().filter(|result| result.passed()).count();
+                writeln!(out, "### Check: `{}`\n", report.file)?;
+                writeln!(out, "{} of {} expectations passed.\n", passed, report.results.len())?;
+                writeln!(out, "| Result | Line | Database | Name | Expected | Failures |")?;
+                writeln!(out, "|---|---|---|---|---|---|")?;
+                for result in &report.results {
+                    writeln!(
+                        out,
+                        "| {} | {} | {} | `{}` | {} | {} |",
+                        if result.passed() { "pass" } else { "**FAIL**" },
+                        result.line,
+                        result.database,
+                        result.name,
+                        cell(&result.expected),
+                        cell(&result.failures.join("\n"))
+                    )?;
+                }
+            }
+            Output::Snapshot(summary) => {
+                writeln!(out, "Wrote `{}`: {} files, {} recorded answers.", summary.bundle, summary.files, summary.answers)?;
+            }
+            Output::Schema(schema) => {
+                writeln!(out, "```json\n{}\n```", serde_json::to_string_pretty(schema)?)?;
+            }
+        }
+        Ok(())
+    }
+}
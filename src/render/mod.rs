@@ -0,0 +1,9 @@
+pub mod getent;
+pub mod graph;
+pub mod markdown;
+pub mod output;
+pub mod renderer;
+pub mod report;
+pub mod schema;
+pub mod structured;
+pub mod text;
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::audit::finding::AuditReport;
+use crate::check::expectations::CheckReport;
+use crate::explain::decision_tree::{DecisionStep, DecisionTree, Shadowed};
+use crate::explain::why_not::WhyNot;
+use crate::nss::group::GroupEntry;
+use crate::nss::hosts::HostEntry;
+use crate::nss::passwd::UserEntry;
+use crate::sysroot::snapshot::RecordedAnswer;
+
+/// A lookup as the resolvers produce it. Structured renderers print it as
+/// a [`super::schema::LookupDocument`], so fields here can change without
+/// breaking consumers.
+pub struct ResolutionResult {
+    pub name: String,
+    pub result: Option<String>,
+    pub host_id: Option<String>,
+    /// The flat trace the tree is built from, for checks and snapshots.
+    pub steps: Vec<DecisionStep>,
+    pub tree: DecisionTree,
+    /// Every address the answering source returned, for host lookups.
+    pub addresses: Vec<String>,
+    /// The whole entry, when the answering source provides one.
+    pub record: Option<Record>,
+    /// Live source answers seen during the lookup, kept for snapshots.
+    pub answers: Vec<RecordedAnswer>,
+    /// Why an `--expect`ed address was not the answer.
+    pub why_not: Option<WhyNot>,
+    /// Sources consulted after the decision, with `--all-sources`.
+    pub shadowed: Option<Shadowed>,
+    /// What the lookup ran against, from [`crate::context::ResolveContext::label`].
+    pub target: String,
+    pub elapsed: Duration,
+}
+
+/// A whole database entry: what a user or group lookup returned, or what a
+/// source lists when enumerating.
+#[derive(Debug, Clone)]
+pub enum Record {
+    Host(HostEntry),
+    User(UserEntry),
+    Group(GroupEntry),
+}
+
+impl Record {
+    /// The entry as `getent` prints it.
+    pub fn getent_line(&self) -> String {
+        match self {
+            Record::Host(entry) => format!("{:<15} {}", entry.ip, entry.names.join(" ")),
+            Record::User(entry) => entry.getent_line(),
+            Record::Group(entry) => entry.getent_line(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Database {
+    Hosts,
+    Passwd,
+    Group,
+}
+
+impl Database {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Database::Hosts => "hosts",
+            Database::Passwd => "passwd",
+            Database::Group => "group",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+    pub bundle: String,
+    pub files: usize,
+    pub answers: usize,
+}
+
+/// What a subcommand produces. Every renderer takes one of these, so a new
+/// subcommand only needs a new variant and the renderers that make sense
+/// for it.
+pub enum Output {
+    Lookup {
+        database: Database,
+        result: Box<ResolutionResult>,
+        /// Show the resolution tree, not just the answer.
+        why: bool,
+    },
+    Audit {
+        suite: String,
+        report: AuditReport,
+        /// The `file://` URI of the directory audited as `/`, for SARIF.
+        root_uri: Option<String>,
+    },
+    Check {
+        report: CheckReport,
+        /// The `file://` URI expectation file paths are relative to, for SARIF.
+        base_uri: String,
+    },
+    Snapshot(SnapshotSummary),
+    /// The JSON Schema of lookup documents.
+    Schema(schemars::schema::RootSchema),
+}
+
+impl Output {
+    /// The subcommand the output came from, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Output::Lookup { database: Database::Hosts, .. } => "host lookups",
+            Output::Lookup { database: Database::Passwd, .. } => "user lookups",
+            Output::Lookup { database: Database::Group, .. } => "group lookups",
+            Output::Audit { .. } => "audits",
+            Output::Check { .. } => "checks",
+            Output::Snapshot(_) => "snapshots",
+            Output::Schema(_) => "the schema",
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::{self, IsTerminal, Write};
+
+use clap::ValueEnum;
+
+use super::output::Output;
+use super::{getent, graph, markdown, report, structured, text};
+
+/// Output renderers. Not every format fits every subcommand: getent, dot
+/// and mermaid print lookups, SARIF and JUnit print audits and checks.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[value(alias = "text")]
+    Plain,
+    Color,
+    Json,
+    Ndjson,
+    Yaml,
+    Getent,
+    Markdown,
+    Sarif,
+    Junit,
+    Dot,
+    Mermaid,
+}
+
+/// Prints an [`Output`] in one format. Renderers refuse outputs the format
+/// has no way to express instead of printing something partial.
+pub trait Renderer {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()>;
+}
+
+pub fn for_format(format: OutputFormat) -> Box<dyn Renderer> {
+    match format {
+        OutputFormat::Plain => Box::new(text::Text { color: false }),
+        OutputFormat::Color => Box::new(text::Text { color: true }),
+        OutputFormat::Json => Box::new(structured::Json),
+        OutputFormat::Ndjson => Box::new(structured::Ndjson),
+        OutputFormat::Yaml => Box::new(structured::Yaml),
+        OutputFormat::Getent => Box::new(getent::Getent),
+        OutputFormat::Markdown => Box::new(markdown::Markdown),
+        OutputFormat::Sarif => Box::new(report::Sarif),
+        OutputFormat::Junit => Box::new(report::Junit),
+        OutputFormat::Dot => Box::new(graph::Graph { mermaid: false }),
+        OutputFormat::Mermaid => Box::new(graph::Graph { mermaid: true }),
+    }
+}
+
+/// `--format`, then `--json`, then color on a terminal unless `NO_COLOR` is set.
+pub fn select(format: Option<OutputFormat>, json: bool) -> OutputFormat {
+    match format {
+        Some(format) => format,
+        None if json => OutputFormat::Json,
+        None if io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() => OutputFormat::Color,
+        None => OutputFormat::Plain,
+    }
+}
+
+/// The error for a format that cannot express `output`.
+pub fn unsupported(format: &str, output: &Output) -> anyhow::Error {
+    anyhow::anyhow!("{} output is not available for {}", format, output.kind())
+}
@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use super::output::Output;
+use super::renderer::{unsupported, Renderer};
+use crate::audit::{junit, sarif};
+
+pub struct Sarif;
+
+impl Renderer for Sarif {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let log = match output {
+            Output::Audit { report, root_uri, .. } => sarif::sarif_log(report, root_uri.as_deref()),
+            // Locations are in the expectations file, not the target system
+            Output::Check { report, base_uri } => sarif::sarif_log(&report.to_audit_report(), Some(base_uri)),
+            _ => return Err(unsupported("sarif", output)),
+        };
+        writeln!(out, "{}", serde_json::to_string_pretty(&log)?)?;
+        Ok(())
+    }
+}
+
+pub struct Junit;
+
+impl Renderer for Junit {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let xml = match output {
+            Output::Audit { suite, report, .. } => {
+                let suite = format!("resolve audit {}", suite);
+                junit::junit_xml(&suite, &junit::audit_cases(&suite, report), &report.notes)
+            }
+            Output::Check { report, .. } => junit::junit_xml("resolve check", &report.junit_cases("resolve check"), &[]),
+            _ => return Err(unsupported("junit", output)),
+        };
+        write!(out, "{}", xml)?;
+        Ok(())
+    }
+}
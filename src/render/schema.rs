@@ -0,0 +1,372 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::output::{Database, Record, ResolutionResult};
+use crate::audit::finding::{AuditReport, Finding};
+use crate::check::expectations::{CheckReport, CheckResult};
+use crate::explain::decision_tree::{Conflict, DecisionNode, DecisionStep, Outcome};
+use crate::explain::why_not::WhyNot;
+use crate::nss::nsswitch::{NssAction, NssStatus};
+
+/// Bumped whenever a field is removed, renamed or changes meaning. Adding a
+/// field does not change the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One lookup as `--format json`, `ndjson` and `yaml` print it.
+#[derive(Serialize, JsonSchema)]
+pub struct LookupDocument {
+    pub schema_version: u32,
+    pub database: Database,
+    /// The name as given on the command line.
+    pub name: String,
+    pub found: bool,
+    /// What the lookup returned; null when the name was not found.
+    pub answer: Option<Answer>,
+    /// For user and group lookups inside a user namespace, the id on the host.
+    pub host_id: Option<u32>,
+    /// Wall-clock time of the whole lookup, in milliseconds.
+    pub duration_ms: f64,
+    pub provenance: Provenance,
+    /// The resolution tree, in the order sources were consulted.
+    pub tree: Vec<Node>,
+    /// With `--why-not`, why the expected address was not the answer.
+    pub why_not: Option<WhyNot>,
+    /// With `--all-sources`, shadowed sources whose answer differs.
+    pub conflicts: Option<Vec<Conflict>>,
+}
+
+/// The typed answer of a lookup, tagged by `kind`.
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Answer {
+    /// Every address the answering source returned, the chosen one first.
+    Addresses { addresses: Vec<String> },
+    /// A passwd entry. Fields other than the uid are null when the
+    /// answering source does not provide a full entry; the uid is null when
+    /// the source answered with something that is not a number.
+    User {
+        name: String,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        gecos: Option<String>,
+        home: Option<String>,
+        shell: Option<String>,
+    },
+    /// A group entry; `members` is null when the source does not list them,
+    /// and `gid` when the source answered with something that is not a number.
+    Group {
+        name: String,
+        gid: Option<u32>,
+        members: Option<Vec<String>>,
+    },
+}
+
+/// Where a document came from.
+#[derive(Serialize, JsonSchema)]
+pub struct Provenance {
+    pub tool: String,
+    pub version: String,
+    /// Seconds since the Unix epoch.
+    pub generated_at: u64,
+    /// What the lookup ran against: "local system", "pid 42 (container web)",
+    /// "netns blue", "root /mnt" and so on.
+    pub target: String,
+    /// The source whose status ended the nsswitch walk.
+    pub answered_by: Option<String>,
+}
+
+/// One node of the resolution tree.
+#[derive(Serialize, JsonSchema)]
+pub struct Node {
+    pub source: String,
+    pub outcome: NodeOutcome,
+    pub reason: String,
+    /// The NSS status the source reported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<NssStatus>,
+    /// What the walk did with that status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<NssAction>,
+    /// The explicit `[STATUS=action]` criterion that chose the action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub criterion: Option<String>,
+    /// False for sources the real lookup never reaches.
+    pub taken: bool,
+    /// The branch not taken, or what would reach a source not taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// How long the source took, for nodes that stand for a whole source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+/// What a step produced, tagged by `kind`.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NodeOutcome {
+    Match { value: String },
+    NoMatch,
+    Error { message: String },
+    Unsupported { reason: String },
+    TryAgain { message: String },
+    Info { message: String },
+}
+
+impl From<&Outcome> for NodeOutcome {
+    fn from(outcome: &Outcome) -> Self {
+        match outcome {
+            Outcome::Match(value) => NodeOutcome::Match { value: value.clone() },
+            Outcome::NoMatch => NodeOutcome::NoMatch,
+            Outcome::Error(message) => NodeOutcome::Error { message: message.clone() },
+            Outcome::Unsupported(reason) => NodeOutcome::Unsupported { reason: reason.clone() },
+            Outcome::TryAgain(message) => NodeOutcome::TryAgain { message: message.clone() },
+            Outcome::Info(message) => NodeOutcome::Info { message: message.clone() },
+        }
+    }
+}
+
+impl From<NodeOutcome> for Outcome {
+    fn from(outcome: NodeOutcome) -> Self {
+        match outcome {
+            NodeOutcome::Match { value } => Outcome::Match(value),
+            NodeOutcome::NoMatch => Outcome::NoMatch,
+            NodeOutcome::Error { message } => Outcome::Error(message),
+            NodeOutcome::Unsupported { reason } => Outcome::Unsupported(reason),
+            NodeOutcome::TryAgain { message } => Outcome::TryAgain(message),
+            NodeOutcome::Info { message } => Outcome::Info(message),
+        }
+    }
+}
+
+/// Milliseconds to microsecond precision; finer digits are timer noise.
+pub fn milliseconds(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// A step of a flat trace, such as the one a check result carries.
+impl From<&DecisionStep> for Node {
+    fn from(step: &DecisionStep) -> Self {
+        Node {
+            source: step.source.clone(),
+            outcome: (&step.outcome).into(),
+            reason: step.reason.clone(),
+            status: None,
+            action: None,
+            criterion: None,
+            taken: true,
+            condition: None,
+            duration_ms: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl From<&DecisionNode> for Node {
+    fn from(node: &DecisionNode) -> Self {
+        Node {
+            source: node.source.clone(),
+            outcome: (&node.outcome).into(),
+            reason: node.reason.clone(),
+            status: node.status,
+            action: node.action,
+            criterion: node.criterion.clone(),
+            taken: node.taken,
+            condition: node.condition.clone(),
+            duration_ms: node.elapsed.map(milliseconds),
+            children: node.children.iter().map(Node::from).collect(),
+        }
+    }
+}
+
+fn answer(database: Database, result: &ResolutionResult) -> Option<Answer> {
+    let value = result.result.as_ref()?;
+    Some(match (database, &result.record) {
+        (Database::Hosts, _) => Answer::Addresses {
+            addresses: if result.addresses.is_empty() { vec![value.clone()] } else { result.addresses.clone() },
+        },
+        (_, Some(Record::User(entry))) => Answer::User {
+            name: entry.name.clone(),
+            uid: Some(entry.uid),
+            gid: Some(entry.gid),
+            gecos: Some(entry.gecos.clone()),
+            home: Some(entry.home.clone()),
+            shell: Some(entry.shell.clone()),
+        },
+        (_, Some(Record::Group(entry))) => Answer::Group {
+            name: entry.name.clone(),
+            gid: Some(entry.gid),
+            members: Some(entry.members.clone()),
+        },
+        (Database::Passwd, _) => Answer::User {
+            name: result.name.clone(),
+            uid: value.parse().ok(),
+            gid: None,
+            gecos: None,
+            home: None,
+            shell: None,
+        },
+        (Database::Group, _) => Answer::Group {
+            name: result.name.clone(),
+            gid: value.parse().ok(),
+            members: None,
+        },
+    })
+}
+
+impl LookupDocument {
+    pub fn new(database: Database, result: &ResolutionResult) -> Self {
+        let answered_by = result
+            .tree
+            .nodes
+            .iter()
+            .rev()
+            .find(|node| node.taken && node.action.is_some())
+            .map(|node| node.source.clone());
+        LookupDocument {
+            schema_version: SCHEMA_VERSION,
+            database,
+            name: result.name.clone(),
+            found: result.result.is_some(),
+            answer: answer(database, result),
+            host_id: result.host_id.as_ref().and_then(|id| id.parse().ok()),
+            duration_ms: milliseconds(result.elapsed),
+            provenance: Provenance {
+                tool: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                generated_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+                target: result.target.clone(),
+                answered_by,
+            },
+            tree: result.tree.nodes.iter().map(Node::from).collect(),
+            why_not: result.why_not.clone(),
+            conflicts: result.shadowed.as_ref().map(|shadowed| shadowed.conflicts.clone()),
+        }
+    }
+}
+
+/// An audit as `--format json` and `yaml` print it.
+#[derive(Serialize)]
+pub struct AuditDocument<'a> {
+    pub schema_version: u32,
+    /// Most severe first, then in file order.
+    pub findings: &'a [Finding],
+    /// Checks that were skipped or degraded, and why.
+    pub notes: &'a [String],
+}
+
+impl<'a> AuditDocument<'a> {
+    pub fn new(report: &'a AuditReport) -> Self {
+        AuditDocument {
+            schema_version: SCHEMA_VERSION,
+            findings: &report.findings,
+            notes: &report.notes,
+        }
+    }
+}
+
+/// A check as `--format json` and `yaml` print it.
+#[derive(Serialize)]
+pub struct CheckDocument {
+    pub schema_version: u32,
+    /// The expectations file.
+    pub file: String,
+    pub passed: bool,
+    pub results: Vec<CheckResultDocument>,
+}
+
+/// One expectation and how its lookup went.
+#[derive(Serialize)]
+pub struct CheckResultDocument {
+    pub database: &'static str,
+    pub name: String,
+    /// The line of the expectation in its file.
+    pub line: usize,
+    pub expected: String,
+    pub actual: Option<String>,
+    /// The nsswitch source the answer came from.
+    pub source: Option<String>,
+    pub passed: bool,
+    pub failures: Vec<String>,
+    /// The trace of the lookup, in the order sources were consulted.
+    pub steps: Vec<Node>,
+}
+
+impl From<&CheckResult> for CheckResultDocument {
+    fn from(result: &CheckResult) -> Self {
+        CheckResultDocument {
+            database: result.database,
+            name: result.name.clone(),
+            line: result.line,
+            expected: result.expected.clone(),
+            actual: result.actual.clone(),
+            source: result.source.clone(),
+            passed: result.passed(),
+            failures: result.failures.clone(),
+            steps: result.steps.iter().map(Node::from).collect(),
+        }
+    }
+}
+
+impl CheckDocument {
+    pub fn new(report: &CheckReport) -> Self {
+        CheckDocument {
+            schema_version: SCHEMA_VERSION,
+            file: report.file.clone(),
+            passed: report.passed(),
+            results: report.results.iter().map(CheckResultDocument::from).collect(),
+        }
+    }
+}
+
+/// The JSON Schema `resolve schema` prints.
+pub fn lookup_schema() -> RootSchema {
+    let mut schema = schemars::schema_for!(LookupDocument);
+    let metadata = schema.schema.metadata();
+    metadata.title = Some(format!("resolve lookup, schema version {}", SCHEMA_VERSION));
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(value: &str) -> ResolutionResult {
+        ResolutionResult {
+            name: "deploy".to_string(),
+            result: Some(value.to_string()),
+            host_id: None,
+            steps: Vec::new(),
+            tree: Default::default(),
+            addresses: Vec::new(),
+            record: None,
+            answers: Vec::new(),
+            why_not: None,
+            shadowed: None,
+            target: "local system".to_string(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn ids_without_entries() {
+        let Some(Answer::User { uid, gid, .. }) = answer(Database::Passwd, &result("1500")) else {
+            panic!("not a user answer");
+        };
+        assert_eq!((uid, gid), (Some(1500), None));
+
+        // Never reported as uid 0
+        let Some(Answer::User { uid, .. }) = answer(Database::Passwd, &result("deploy")) else {
+            panic!("not a user answer");
+        };
+        assert_eq!(uid, None);
+        let Some(Answer::Group { gid, .. }) = answer(Database::Group, &result("x")) else {
+            panic!("not a group answer");
+        };
+        assert_eq!(gid, None);
+    }
+}
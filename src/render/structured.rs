@@ -0,0 +1,105 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use super::output::Output;
+use super::schema::{AuditDocument, CheckDocument, CheckResultDocument, LookupDocument, SCHEMA_VERSION};
+use super::renderer::Renderer;
+use crate::audit::finding::Finding;
+
+/// One NDJSON record of a check: the result with the file it came from.
+#[derive(Serialize)]
+struct CheckRecord<'a> {
+    schema_version: u32,
+    file: &'a str,
+    #[serde(flatten)]
+    result: CheckResultDocument,
+}
+
+#[derive(Serialize)]
+struct FindingRecord<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    finding: &'a Finding,
+}
+
+#[derive(Serialize)]
+struct NoteRecord<'a> {
+    schema_version: u32,
+    note: &'a str,
+}
+
+pub struct Json;
+
+impl Renderer for Json {
+    /// Lookups, audits and checks print as versioned documents.
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let json = match output {
+            Output::Lookup { database, result, .. } => serde_json::to_string_pretty(&LookupDocument::new(*database, result))?,
+            Output::Audit { report, .. } => serde_json::to_string_pretty(&AuditDocument::new(report))?,
+            Output::Check { report, .. } => serde_json::to_string_pretty(&CheckDocument::new(report))?,
+            Output::Snapshot(summary) => serde_json::to_string_pretty(summary)?,
+            Output::Schema(schema) => serde_json::to_string_pretty(schema)?,
+        };
+        writeln!(out, "{}", json)?;
+        Ok(())
+    }
+}
+
+/// One record per line: a lookup or snapshot is one record, an audit one per
+/// finding and note, a check one per expectation.
+pub struct Ndjson;
+
+impl Renderer for Ndjson {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        match output {
+            Output::Lookup { database, result, .. } => writeln!(out, "{}", serde_json::to_string(&LookupDocument::new(*database, result))?)?,
+            Output::Audit { report, .. } => {
+                for finding in &report.findings {
+                    let record = FindingRecord {
+                        schema_version: SCHEMA_VERSION,
+                        finding,
+                    };
+                    writeln!(out, "{}", serde_json::to_string(&record)?)?;
+                }
+                for note in &report.notes {
+                    let record = NoteRecord {
+                        schema_version: SCHEMA_VERSION,
+                        note,
+                    };
+                    writeln!(out, "{}", serde_json::to_string(&record)?)?;
+                }
+            }
+            Output::Check { report, .. } => {
+                for result in &report.results {
+                    let record = CheckRecord {
+                        schema_version: SCHEMA_VERSION,
+                        file: &report.file,
+                        result: result.into(),
+                    };
+                    writeln!(out, "{}", serde_json::to_string(&record)?)?;
+                }
+            }
+            Output::Snapshot(summary) => writeln!(out, "{}", serde_json::to_string(summary)?)?,
+            Output::Schema(schema) => writeln!(out, "{}", serde_json::to_string(schema)?)?,
+        }
+        Ok(())
+    }
+}
+
+/// The JSON documents as YAML.
+pub struct Yaml;
+
+impl Renderer for Yaml {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        let yaml = match output {
+            Output::Lookup { database, result, .. } => serde_yaml::to_string(&LookupDocument::new(*database, result))?,
+            Output::Audit { report, .. } => serde_yaml::to_string(&AuditDocument::new(report))?,
+            Output::Check { report, .. } => serde_yaml::to_string(&CheckDocument::new(report))?,
+            Output::Snapshot(summary) => serde_yaml::to_string(summary)?,
+            Output::Schema(schema) => serde_yaml::to_string(schema)?,
+        };
+        write!(out, "{}", yaml)?;
+        Ok(())
+    }
+}
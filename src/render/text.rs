@@ -0,0 +1,192 @@
+use std::io::Write;
+
+use super::output::{Database, Output, ResolutionResult};
+use super::renderer::Renderer;
+use crate::audit::finding::Severity;
+use crate::explain::decision_tree::{DecisionNode, Outcome, Shadowed};
+
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const CYAN: &str = "36";
+
+/// The human-readable output, with ANSI colors when `color` is set.
+pub struct Text {
+    pub color: bool,
+}
+
+impl Text {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn outcome(&self, outcome: &Outcome) -> String {
+        let code = match outcome {
+            Outcome::Match(_) => GREEN,
+            Outcome::NoMatch => DIM,
+            Outcome::Error(_) => RED,
+            Outcome::Unsupported(_) | Outcome::TryAgain(_) => YELLOW,
+            Outcome::Info(_) => CYAN,
+        };
+        self.paint(code, &outcome.describe())
+    }
+
+    fn severity(&self, severity: Severity) -> String {
+        let label = format!("{:<6}", severity.as_str().to_uppercase());
+        match severity {
+            Severity::High => self.paint(RED, &label),
+            Severity::Medium => self.paint(YELLOW, &label),
+            Severity::Low => self.paint(CYAN, &label),
+        }
+    }
+
+    /// Prints a resolution tree, numbering children after their parent (3.1,
+    /// 3.2) and annotating each branch with the one not taken.
+    fn tree(&self, out: &mut dyn Write, nodes: &[DecisionNode], prefix: &str) -> std::io::Result<()> {
+        for (i, node) in nodes.iter().enumerate() {
+            let number = format!("{}{}", prefix, i + 1);
+            let indent = " ".repeat(number.len() + 4);
+            if node.taken {
+                writeln!(out, "  {}. {} → {}", number, node.source, self.outcome(&node.outcome))?;
+            } else {
+                let line = format!("  {}. (not taken) {} → {}", number, node.source, node.outcome.describe());
+                writeln!(out, "{}", self.paint(DIM, &line))?;
+            }
+            if !node.reason.is_empty() {
+                writeln!(out, "{}Reason: {}", indent, node.reason)?;
+            }
+            if let (Some(status), Some(action)) = (&node.status, &node.action) {
+                match &node.criterion {
+                    Some(criterion) => writeln!(out, "{}Status: {} → {} {}", indent, status.as_str(), action.as_str(), criterion)?,
+                    None => writeln!(out, "{}Status: {} → {}", indent, status.as_str(), action.as_str())?,
+                }
+            }
+            if let Some(condition) = &node.condition {
+                if node.taken {
+                    writeln!(out, "{}Otherwise: {}", indent, condition)?;
+                } else {
+                    writeln!(out, "{}", self.paint(DIM, &format!("{}Condition: {}", indent, condition)))?;
+                }
+            }
+            self.tree(out, &node.children, &format!("{}.", number))?;
+        }
+        Ok(())
+    }
+
+    /// Prints the steps `--all-sources` consulted after the decision, unless
+    /// `why` already showed them in the tree, and the conflicts among them.
+    fn shadowed(&self, out: &mut dyn Write, shadowed: &Shadowed, why: bool) -> std::io::Result<()> {
+        if !why {
+            writeln!(out, "Shadowed (consulted after the lookup decided):")?;
+            for (i, step) in shadowed.steps.iter().enumerate() {
+                writeln!(out, "  {}. {} → {}", i + 1, step.source, self.outcome(&step.outcome))?;
+                if !step.reason.is_empty() {
+                    writeln!(out, "     Reason: {}", step.reason)?;
+                }
+            }
+        }
+        for conflict in &shadowed.conflicts {
+            let line = match &conflict.winner {
+                Some(winner) => format!("Conflict: {} answers {}, but the lookup returned {}", conflict.source, conflict.answer, winner),
+                None => format!("Conflict: {} answers {}, but the lookup returned nothing", conflict.source, conflict.answer),
+            };
+            writeln!(out, "{}", self.paint(YELLOW, &line))?;
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, out: &mut dyn Write, database: Database, result: &ResolutionResult, why: bool) -> std::io::Result<()> {
+        let name = &result.name;
+        let label = match database {
+            Database::Hosts => "",
+            Database::Passwd => "uid ",
+            Database::Group => "gid ",
+        };
+        match (&result.result, &result.host_id) {
+            (Some(answer), Some(host_id)) => {
+                let answer = self.paint(BOLD, &format!("{}{}", label, answer));
+                writeln!(out, "{} → {} (host {}{})", name, answer, label, host_id)?
+            }
+            (Some(answer), None) => writeln!(out, "{} → {}", name, self.paint(BOLD, &format!("{}{}", label, answer)))?,
+            (None, _) if database == Database::Hosts => writeln!(out, "{} {}", name, self.paint(RED, "not resolved"))?,
+            (None, _) => writeln!(out, "{} {}", name, self.paint(RED, "not found"))?,
+        }
+        if why {
+            writeln!(out, "Resolution path:")?;
+            self.tree(out, &result.tree.nodes, "")?;
+        }
+        if let Some(shadowed) = &result.shadowed {
+            self.shadowed(out, shadowed, why)?;
+        }
+        if let Some(why_not) = &result.why_not {
+            writeln!(out, "Why not {}:", why_not.expected)?;
+            for line in &why_not.explanation {
+                writeln!(out, "  - {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Text {
+    fn render(&self, output: &Output, out: &mut dyn Write) -> anyhow::Result<()> {
+        match output {
+            Output::Lookup { database, result, why } => self.lookup(out, *database, result, *why)?,
+            Output::Audit { report, .. } => {
+                for finding in &report.findings {
+                    writeln!(
+                        out,
+                        "{} {} [{}] {}",
+                        self.severity(finding.severity),
+                        finding.location(),
+                        finding.rule,
+                        finding.message
+                    )?;
+                    if let Some(remediation) = &finding.remediation {
+                        writeln!(out, "       Fix: {}", remediation)?;
+                    }
+                }
+                for note in &report.notes {
+                    writeln!(out, "Note: {}", note)?;
+                }
+                if report.findings.is_empty() {
+                    writeln!(out, "{}", self.paint(GREEN, "No findings"))?;
+                } else {
+                    writeln!(out, "{} finding{}", report.findings.len(), if report.findings.len() == 1 { "" } else { "s" })?;
+                }
+            }
+            Output::Check { report, .. } => {
+                for result in &report.results {
+                    let status = if result.passed() { self.paint(GREEN, "PASS") } else { self.paint(RED, "FAIL") };
+                    writeln!(out, "{} {}:{} {} {} {}", status, report.file, result.line, result.database, result.name, result.expected)?;
+                    for failure in &result.failures {
+                        writeln!(out, "     {}", failure)?;
+                    }
+                    if !result.passed() {
+                        writeln!(out, "     Resolution path:")?;
+                        for (i, step) in result.steps.iter().enumerate() {
+                            writeln!(out, "       {}. {} → {}", i + 1, step.source, self.outcome(&step.outcome))?;
+                            if !step.reason.is_empty() {
+                                writeln!(out, "          Reason: {}", step.reason)?;
+                            }
+                        }
+                    }
+                }
+                let passed = report.results.iter().filter(|result| result.passed()).count();
+                writeln!(out, "{} of {} expectations passed", passed, report.results.len())?;
+            }
+            Output::Snapshot(summary) => {
+                writeln!(out, "Wrote {}: {} files, {} recorded answers", summary.bundle, summary.files, summary.answers)?;
+            }
+            // A schema is JSON whatever the format; there is no plainer way to print it
+            Output::Schema(schema) => writeln!(out, "{}", serde_json::to_string_pretty(schema)?)?,
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,676 @@
+use std::time::Instant;
+
+use crate::context::ResolveContext;
+use crate::dbus::SystemBus;
+use crate::dns;
+use crate::explain::decision_tree::{self, DecisionStep, Outcome, Walk};
+use crate::explain::why_not::{self, Candidate, Stop};
+use crate::nss::nsswitch::{NssAction, NssSource, NssStatus};
+use crate::nss::{group, hosts, passwd, subid};
+use crate::proc;
+use crate::render::output::{Database, Record, ResolutionResult};
+use crate::sources::plugin;
+use crate::sources::registry::Registry;
+use crate::sources::source::Query;
+use crate::sysroot::snapshot::{self, RecordedAnswer};
+
+/// How far a lookup goes past the decision.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Consult every source, reporting those after the decision as shadowed.
+    pub all_sources: bool,
+    /// Consult every source and explain why this address was not the
+    /// answer. Host lookups only.
+    pub expect: Option<String>,
+    /// Ask each resolv.conf nameserver directly as well, recording what
+    /// every server answered. Host lookups only.
+    pub per_server: bool,
+}
+
+/// Walks nsswitch.conf the way glibc does, consulting the sources of a
+/// [`Registry`], and returns the answer with the tree of how it was reached.
+pub struct Resolver {
+    registry: Registry,
+    bus: SystemBus,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new(Registry::builtin())
+    }
+}
+
+/// What one walk of an nsswitch line produced.
+struct Walked {
+    walk: Walk,
+    candidates: Vec<Candidate>,
+    records: Vec<(usize, Record)>,
+    answers: Vec<RecordedAnswer>,
+    /// The source that ended the walk, its status, and the number of steps
+    /// up to and including its decision.
+    stop: Option<(usize, NssStatus, usize)>,
+}
+
+/// Explains which process's files are being read when `--pid` points into a container.
+pub fn context_steps(ctx: &ResolveContext) -> Vec<DecisionStep> {
+    let mut steps = Vec::new();
+    if let Some((sysroot, replay)) = ctx.sysroot.as_ref().and_then(|sysroot| Some((sysroot, sysroot.replay.as_ref()?))) {
+        steps.push(DecisionStep {
+            source: format!("sysroot ({})", sysroot.label),
+            outcome: Outcome::Info(sysroot.summary.clone()),
+            reason: "Files and live answers are replayed from the bundle".to_string(),
+        });
+        // The captured process's container, netns and pod, as seen at capture time
+        steps.extend(replay.manifest.context.iter().cloned());
+        let resolved = match &replay.manifest.resolved_error {
+            Some(e) => format!("unavailable at capture time: {}", e),
+            None if replay.manifest.resolved.is_empty() => "no servers or domains configured".to_string(),
+            None => replay.manifest.resolved.join("; "),
+        };
+        steps.push(DecisionStep {
+            source: "systemd-resolved (snapshot)".to_string(),
+            outcome: Outcome::Info(resolved),
+            reason: "Per-link DNS servers and domains recorded with the bundle".to_string(),
+        });
+        return steps;
+    }
+    if let Some(container) = &ctx.container {
+        let id = container.id.as_deref().map(|id| format!(" ({})", &id[..12])).unwrap_or_default();
+        steps.push(DecisionStep {
+            source: format!("container ({})", container.runtime.as_str()),
+            outcome: Outcome::Info(format!("pid {} runs in container {}{}", ctx.pid.unwrap_or_default(), container.label(), id)),
+            reason: format!("Detected via {}; files are read from the container root", container.evidence.join(", ")),
+        });
+    }
+    if let Some(netns) = &ctx.netns {
+        let applied: Vec<&str> = netns.overrides.iter().filter(|o| o.applied).map(|o| o.target.as_str()).collect();
+        let skipped: Vec<&str> = netns.overrides.iter().filter(|o| !o.applied).map(|o| o.target.as_str()).collect();
+        let mut reason = format!(
+            "ip netns exec bind-mounts {}/{}/* over /etc",
+            proc::namespaces::NETNS_ETC_DIR,
+            netns.name
+        );
+        if !skipped.is_empty() {
+            reason.push_str(&format!(
+                "; {} not mounted in this process, which entered the namespace some other way",
+                skipped.join(", ")
+            ));
+        }
+        steps.push(DecisionStep {
+            source: format!("netns ({})", netns.name),
+            outcome: Outcome::Info(if applied.is_empty() {
+                format!("network namespace {} has no /etc overrides in effect", netns.name)
+            } else {
+                format!("network namespace {} overrides {}", netns.name, applied.join(", "))
+            }),
+            reason,
+        });
+    }
+    if let Some(pod) = &ctx.pod {
+        steps.push(DecisionStep {
+            source: "kubernetes (pod)".to_string(),
+            outcome: Outcome::Info(pod.summary()),
+            reason: format!("Detected via {}", pod.evidence.join(", ")),
+        });
+    }
+    if let Some(sysroot) = &ctx.sysroot {
+        let mut reason = format!(
+            "Files are read from {}; sources that need a running system are reported as unavailable",
+            sysroot.label
+        );
+        if let Some(user) = &sysroot.config_user {
+            reason.push_str(&format!("; the image config starts processes as user {}", user));
+        }
+        steps.push(DecisionStep {
+            source: format!("sysroot ({})", sysroot.label),
+            outcome: Outcome::Info(sysroot.summary.clone()),
+            reason,
+        });
+    }
+    steps
+}
+
+/// Steps for a live source when there is no running system: the answer
+/// recorded in a snapshot, or UNAVAIL.
+fn offline_steps(database: &str, source: &str, name: &str, ctx: &ResolveContext) -> Vec<DecisionStep> {
+    let Some(sysroot) = &ctx.sysroot else {
+        return Vec::new();
+    };
+    if let Some(replay) = &sysroot.replay {
+        if let Some(answer) = replay.answer(database, source, name) {
+            return answer.steps.clone();
+        }
+        return vec![DecisionStep {
+            source: format!("{} (offline)", source),
+            outcome: Outcome::Unsupported("Not recorded in snapshot".to_string()),
+            reason: format!(
+                "{} has no {} answer for {}; pass it to `resolve snapshot` to record one; treated as UNAVAIL",
+                sysroot.label, source, name
+            ),
+        }];
+    }
+    vec![DecisionStep {
+        source: format!("{} (offline)", source),
+        outcome: Outcome::Unsupported("Not available offline".to_string()),
+        reason: format!("{} needs a running system, which {} does not have; treated as UNAVAIL", source, sysroot.label),
+    }]
+}
+
+/// Applies the nsswitch action for the status of the last step. Returns the
+/// status when the walk stops, after recording any explicit criterion that fired.
+fn apply_nss_action(database: &str, sources: &[NssSource], index: usize, steps: &mut Vec<DecisionStep>) -> Option<NssStatus> {
+    let source = &sources[index];
+    let status = NssStatus::from_outcome(&steps.last()?.outcome)?;
+    let (action, criterion) = source.action_for(status);
+    if let Some(criterion) = criterion {
+        let next = match sources.get(index + 1) {
+            Some(next) if action == NssAction::Return => format!("; {} is never consulted", next.name),
+            _ => String::new(),
+        };
+        steps.push(DecisionStep {
+            source: format!("nsswitch ({})", database),
+            outcome: Outcome::Info(format!("{} {}", source.name, criterion.describe())),
+            reason: format!("{} returned {}, so the lookup will {}{}", source.name, status.as_str(), action.as_str(), next),
+        });
+    }
+    (action == NssAction::Return).then_some(status)
+}
+
+/// The answer of a walk that stopped with `status`: the last match, if the
+/// stopping source succeeded.
+fn nss_result(steps: &[DecisionStep], status: NssStatus) -> Option<String> {
+    if status != NssStatus::Success {
+        return None;
+    }
+    steps.iter().rev().find_map(|step| match &step.outcome {
+        Outcome::Match(value) => Some(value.clone()),
+        _ => None,
+    })
+}
+
+/// With `--pid` inside a user namespace, translates the answer to the id it
+/// has on the host and names the host user the range was delegated to.
+fn map_to_host_id(result: Option<&str>, ctx: &ResolveContext, database: Database, steps: &mut Vec<DecisionStep>) -> Option<String> {
+    let userns = ctx.userns.as_ref()?;
+    let id: u32 = result?.parse().ok()?;
+    let (label, map_file, map, subid_file) = match database {
+        Database::Group => ("gid", "gid_map", &userns.gid_map, "/etc/subgid"),
+        _ => ("uid", "uid_map", &userns.uid_map, "/etc/subuid"),
+    };
+    let pid = ctx.pid.unwrap_or_default();
+
+    let Some((host_id, range)) = proc::userns::to_host_id(map, id) else {
+        steps.push(DecisionStep {
+            source: format!("userns (/proc/{}/{})", pid, map_file),
+            outcome: Outcome::Info(format!("{} {} is unmapped", label, id)),
+            reason: format!("No range covers {} {}; on the host it appears as the overflow id (65534)", label, id),
+        });
+        return None;
+    };
+
+    let subids = subid::parse_subid_file(subid_file).unwrap_or_default();
+    let owner = match subid::find_subid_owner(host_id, &subids) {
+        Some(sub) => format!("host range delegated to {} ({}:{})", sub.owner, sub.source, sub.line),
+        None => {
+            let host_name = match database {
+                Database::Group => group::parse_group_file("/etc/group")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|g| g.gid == host_id)
+                    .map(|g| g.name),
+                _ => passwd::parse_passwd_file("/etc/passwd")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|u| u.uid == host_id)
+                    .map(|u| u.name),
+            };
+            match host_name {
+                Some(host_name) => format!("host {} {} belongs to {}, not to a {} range", label, host_id, host_name, subid_file),
+                None => format!("host {} {} is not in {}", label, host_id, subid_file),
+            }
+        }
+    };
+    steps.push(DecisionStep {
+        source: format!("userns (/proc/{}/{})", pid, map_file),
+        outcome: Outcome::Info(format!("{} {} inside → host {} {}", label, id, label, host_id)),
+        reason: format!("Mapped by range {}; {}", range.describe(), owner),
+    });
+    Some(host_id.to_string())
+}
+
+/// Every address the source that decided the lookup returned, in order and
+/// without the ones it filters out; the answer alone when no source kept a
+/// list (resolved and mDNS report what they return, files every line).
+fn host_addresses(result: Option<&str>, decided: Option<usize>, candidates: &[Candidate]) -> Vec<String> {
+    let Some(result) = result else {
+        return Vec::new();
+    };
+    let mut addresses: Vec<String> = Vec::new();
+    for candidate in candidates.iter().filter(|c| Some(c.index) == decided && c.filtered.is_none()) {
+        if !addresses.iter().any(|a| why_not::same_address(a, &candidate.address)) {
+            addresses.push(candidate.address.clone());
+        }
+    }
+    if !addresses.iter().any(|a| why_not::same_address(a, result)) {
+        addresses.insert(0, result.to_string());
+    }
+    addresses
+}
+
+impl Resolver {
+    pub fn new(registry: Registry) -> Self {
+        Resolver::with_bus(registry, SystemBus::default())
+    }
+
+    /// A resolver whose D-Bus backed sources use `bus` rather than the
+    /// system bus.
+    pub fn with_bus(registry: Registry, bus: SystemBus) -> Self {
+        Resolver { registry, bus }
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// The system bus connection every lookup shares.
+    pub fn bus(&self) -> &SystemBus {
+        &self.bus
+    }
+
+    pub async fn resolve(&self, database: Database, name: &str, ctx: &ResolveContext, options: &Options) -> ResolutionResult {
+        match database {
+            Database::Hosts => self.resolve_host(name, ctx, options).await,
+            Database::Passwd | Database::Group => self.resolve_id(database, name, ctx, options).await,
+        }
+    }
+
+    /// Consults `sources` in order, appending their steps to `steps`. With
+    /// `all_sources` or `expect`, the sources after the decision are
+    /// consulted too.
+    async fn walk(
+        &self,
+        database: Database,
+        name: &str,
+        ctx: &ResolveContext,
+        sources: &[NssSource],
+        steps: &mut Vec<DecisionStep>,
+        options: &Options,
+    ) -> Walked {
+        let mut walked = Walked {
+            walk: Walk::new(database.as_str(), sources),
+            candidates: Vec::new(),
+            records: Vec::new(),
+            answers: Vec::new(),
+            stop: None,
+        };
+        for (index, source) in sources.iter().enumerate() {
+            let first = steps.len();
+            let consulted = Instant::now();
+            let implementation = self.registry.get(&source.name).filter(|s| s.databases().contains(&database));
+            let live = implementation.as_ref().is_some_and(|s| s.live());
+            match implementation {
+                Some(_) if live && !ctx.is_live() => steps.extend(offline_steps(database.as_str(), &source.name, name, ctx)),
+                Some(implementation) => {
+                    let query = Query {
+                        database,
+                        name,
+                        source,
+                        ctx,
+                        bus: &self.bus,
+                        exhaustive: options.expect.is_some(),
+                        per_server: options.per_server,
+                    };
+                    let lookup = implementation.lookup(&query).await;
+                    steps.extend(lookup.steps);
+                    // The walk acts on the last step, which must be this source's
+                    if steps.len() == first {
+                        steps.push(DecisionStep {
+                            source: source.name.clone(),
+                            outcome: Outcome::Error("Source returned no result".to_string()),
+                            reason: "A source that reports no status is treated as UNAVAIL".to_string(),
+                        });
+                    }
+                    walked.candidates.extend(lookup.candidates.into_iter().map(|candidate| Candidate {
+                        index,
+                        source: source.name.clone(),
+                        ..candidate
+                    }));
+                    walked.records.extend(lookup.record.map(|record| (index, record)));
+                }
+                None => steps.push(DecisionStep {
+                    source: format!("{} (unsupported)", source.name),
+                    outcome: Outcome::Unsupported(match self.registry.plugin_dirs() {
+                        [] => "Source not implemented".to_string(),
+                        dirs => format!(
+                            "Source not implemented and no {}{} in {}",
+                            plugin::PLUGIN_PREFIX,
+                            source.name,
+                            dirs.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ")
+                        ),
+                    }),
+                    reason: "glibc reports UNAVAIL when a module cannot be loaded".to_string(),
+                }),
+            }
+
+            // Keeps the steps a live source produced so `resolve snapshot` can store them
+            if live && ctx.is_live() {
+                walked.answers.push(RecordedAnswer {
+                    database: database.as_str().to_string(),
+                    source: source.name.clone(),
+                    name: name.to_string(),
+                    steps: steps[first..].to_vec(),
+                    host_id: None,
+                });
+            }
+            walked.walk.spans.push(decision_tree::SourceSpan {
+                index,
+                steps: first..steps.len(),
+                elapsed: consulted.elapsed(),
+            });
+
+            if walked.stop.is_some() {
+                continue;
+            }
+            if let Some(status) = apply_nss_action(database.as_str(), sources, index, steps) {
+                walked.stop = Some((index, status, steps.len()));
+                walked.walk.decided = Some(index);
+                if !options.all_sources && options.expect.is_none() {
+                    break;
+                }
+            }
+        }
+        walked
+    }
+
+    /// With `expect` or `all_sources`, every source is consulted, including
+    /// those after the walk stops: `expect` explains why that address was
+    /// not the answer, `all_sources` reports the later sources as shadowed.
+    pub async fn resolve_host(&self, name: &str, ctx: &ResolveContext, options: &Options) -> ResolutionResult {
+        let started = Instant::now();
+        let expect = options.expect.as_deref();
+        let mut steps = context_steps(ctx);
+
+        // getaddrinfo short-circuits numeric input before nsswitch is consulted
+        if let Some(numeric) = dns::numeric::parse_numeric_host(name) {
+            let normalized = match &numeric.scope_id {
+                Some(scope) => format!("{}%{}", numeric.addr, scope),
+                None => numeric.addr.to_string(),
+            };
+            let mut reason = format!("Parsed as numeric address ({}); no NSS sources consulted", numeric.form);
+            for note in &numeric.notes {
+                reason.push_str("; ");
+                reason.push_str(note);
+            }
+            steps.push(DecisionStep {
+                source: "numeric (inet_aton/inet_pton)".to_string(),
+                outcome: Outcome::Match(normalized.clone()),
+                reason,
+            });
+            let why_not = expect.map(|expected| why_not::explain_why_not(expected, Some(&normalized), &steps, &[], None));
+            // getent reverse-resolves keys inet_pton accepts; the name comes
+            // from /etc/hosts only, as no NSS source is walked here
+            let record = name
+                .parse::<std::net::IpAddr>()
+                .ok()
+                .and_then(|address| {
+                    let entries = ctx.hosts().ok()?;
+                    hosts::resolve_address_from_hosts(&address, &entries).cloned()
+                })
+                .map(Record::Host);
+            return ResolutionResult {
+                name: name.to_string(),
+                result: Some(normalized.clone()),
+                host_id: None,
+                tree: decision_tree::build_decision_tree(&steps, &Default::default()),
+                steps,
+                addresses: vec![normalized],
+                record,
+                answers: Vec::new(),
+                why_not,
+                shadowed: None,
+                target: ctx.label(),
+                elapsed: started.elapsed(),
+            };
+        }
+
+        let normalized = match dns::names::normalize_host_name(name) {
+            Ok(normalized) => normalized,
+            Err(e) => {
+                steps.push(DecisionStep {
+                    source: "normalize (AI_IDN)".to_string(),
+                    outcome: Outcome::Error(e),
+                    reason: "Name rejected before any NSS source was consulted".to_string(),
+                });
+                let why_not = expect.map(|expected| why_not::explain_why_not(expected, None, &steps, &[], None));
+                return ResolutionResult {
+                    name: name.to_string(),
+                    result: None,
+                    host_id: None,
+                    tree: decision_tree::build_decision_tree(&steps, &Default::default()),
+                    steps,
+                    addresses: Vec::new(),
+                    record: None,
+                    answers: Vec::new(),
+                    why_not,
+                    shadowed: None,
+                    target: ctx.label(),
+                    elapsed: started.elapsed(),
+                };
+            }
+        };
+        if normalized.idna_applied {
+            steps.push(DecisionStep {
+                source: "normalize (AI_IDN)".to_string(),
+                outcome: Outcome::Info(format!("{} → {}", name, normalized.ascii)),
+                reason: "Internationalized name converted to punycode; NSS sources see the ASCII form".to_string(),
+            });
+        }
+        let name_ascii = normalized.ascii.as_str();
+
+        if let Some(domain) = dns::names::special_use_domain(name_ascii) {
+            steps.push(DecisionStep {
+                source: format!("special-use ({} .{})", domain.rfc(), domain.zone()),
+                outcome: Outcome::Info(format!("{} is under the special-use domain .{}", name_ascii, domain.zone())),
+                reason: domain.stack_behavior().to_string(),
+            });
+        }
+
+        let nss_order = ctx.nsswitch().unwrap_or_default();
+        let sources = match nss_order.hosts.as_slice() {
+            [] => vec![NssSource::new("files"), NssSource::new("dns")],
+            sources => sources.to_vec(),
+        };
+        let walked = self.walk(Database::Hosts, name_ascii, ctx, &sources, &mut steps, options).await;
+        let candidates = walked.candidates;
+        let stop = walked.stop.map(|(index, status, end)| {
+            let source = &sources[index];
+            let (_, criterion) = source.action_for(status);
+            let ended = Stop {
+                index,
+                source: source.name.clone(),
+                status: status.as_str(),
+                criterion: criterion.map(|criterion| criterion.describe()),
+            };
+            (ended, status, end)
+        });
+
+        let result = stop.as_ref().and_then(|(_, status, end)| nss_result(&steps[..*end], *status));
+        let why_not = expect.map(|expected| {
+            why_not::explain_why_not(expected, result.as_deref(), &steps, &candidates, stop.as_ref().map(|(stop, _, _)| stop))
+        });
+        let tree = decision_tree::build_decision_tree(&steps, &walked.walk);
+        let addresses = host_addresses(result.as_deref(), walked.walk.decided, &candidates);
+        let record = walked
+            .walk
+            .decided
+            .filter(|_| result.is_some())
+            .and_then(|decided| walked.records.into_iter().find(|(index, _)| *index == decided))
+            .map(|(_, record)| record);
+        // Sources consulted after the walk stopped are not part of the path taken
+        let later = match &stop {
+            Some((_, _, end)) => steps.split_off(*end),
+            None => Vec::new(),
+        };
+        let shadowed = (options.all_sources && stop.is_some()).then(|| {
+            decision_tree::shadow(later, result.as_deref(), |step, winner| {
+                let source = step.source.split_whitespace().next().unwrap_or_default();
+                candidates
+                    .iter()
+                    .any(|c| c.source == source && why_not::same_address(&c.address, winner))
+            })
+        });
+        ResolutionResult {
+            name: name.to_string(),
+            result,
+            host_id: None,
+            steps,
+            tree,
+            addresses,
+            record,
+            answers: walked.answers,
+            why_not,
+            shadowed,
+            target: ctx.label(),
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// User and group lookups. With `all_sources`, sources after the
+    /// decision are consulted too and reported as shadowed.
+    async fn resolve_id(&self, database: Database, name: &str, ctx: &ResolveContext, options: &Options) -> ResolutionResult {
+        let started = Instant::now();
+        let mut steps = context_steps(ctx);
+
+        let nss_order = ctx.nsswitch().unwrap_or_default();
+        let mut sources = match database {
+            Database::Group => nss_order.group.clone(),
+            _ => nss_order.passwd.clone(),
+        };
+        if sources.is_empty() {
+            sources = vec![NssSource::new("files")];
+        }
+        let walked = self.walk(database, name, ctx, &sources, &mut steps, options).await;
+        let result = walked.stop.and_then(|(_, status, end)| nss_result(&steps[..end], status));
+
+        let mut mapped = Vec::new();
+        let mut answers = walked.answers;
+        let host_id = match ctx.sysroot.as_ref().and_then(|sysroot| sysroot.replay.as_ref()) {
+            // The translation needs the live process, so snapshots record it
+            Some(replay) => replay.answer(database.as_str(), snapshot::USERNS_SOURCE, name).and_then(|answer| {
+                mapped.extend(answer.steps.iter().cloned());
+                answer.host_id.clone()
+            }),
+            None => {
+                let host_id = map_to_host_id(result.as_deref(), ctx, database, &mut mapped);
+                if !mapped.is_empty() {
+                    answers.push(RecordedAnswer {
+                        database: database.as_str().to_string(),
+                        source: snapshot::USERNS_SOURCE.to_string(),
+                        name: name.to_string(),
+                        steps: mapped.clone(),
+                        host_id: host_id.clone(),
+                    });
+                }
+                host_id
+            }
+        };
+        let tree = decision_tree::build_decision_tree(&[steps.as_slice(), &mapped].concat(), &walked.walk);
+        let record = walked
+            .walk
+            .decided
+            .filter(|_| result.is_some())
+            .and_then(|decided| walked.records.into_iter().find(|(index, _)| *index == decided))
+            .map(|(_, record)| record);
+
+        let shadowed = walked.stop.filter(|_| options.all_sources).map(|(_, _, end)| {
+            decision_tree::shadow(steps.split_off(end), result.as_deref(), |step, winner| {
+                matches!(&step.outcome, Outcome::Match(answer) if answer == winner)
+            })
+        });
+        steps.extend(mapped);
+        ResolutionResult {
+            name: name.to_string(),
+            result,
+            host_id,
+            steps,
+            tree,
+            addresses: Vec::new(),
+            record,
+            answers,
+            why_not: None,
+            shadowed,
+            target: ctx.label(),
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::nss::nsswitch;
+    use crate::sources::source::{Lookup, Source};
+
+    /// A source that always records `steps`.
+    struct Fixed(Vec<DecisionStep>);
+
+    #[async_trait]
+    impl Source for Fixed {
+        fn databases(&self) -> &[Database] {
+            &[Database::Hosts]
+        }
+
+        async fn lookup(&self, _query: &Query<'_>) -> Lookup {
+            Lookup {
+                steps: self.0.clone(),
+                ..Lookup::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn silent_sources_are_unavail() {
+        let mut registry = Registry::default();
+        let absent = DecisionStep {
+            source: "absent".to_string(),
+            outcome: Outcome::NoMatch,
+            reason: String::new(),
+        };
+        registry.register("absent", Fixed(vec![absent]));
+        registry.register("silent", Fixed(Vec::new()));
+        let resolver = Resolver::with_bus(registry, SystemBus::default());
+        // [NOTFOUND=return] on silent must not fire on absent's status
+        let sources = nsswitch::parse_sources("absent silent [NOTFOUND=return] absent");
+        let ctx = ResolveContext::new(None, None);
+
+        let mut steps = Vec::new();
+        let walked = resolver.walk(Database::Hosts, "db", &ctx, &sources, &mut steps, &Options::default()).await;
+        assert_eq!(steps[1].source, "silent");
+        assert!(matches!(steps[1].outcome, Outcome::Error(_)));
+        assert_eq!(walked.walk.spans.len(), 3);
+        let tree = decision_tree::build_decision_tree(&steps, &walked.walk);
+        assert_eq!(tree.nodes[1].status, Some(NssStatus::Unavail));
+    }
+
+    #[tokio::test]
+    async fn tryagain_criteria_fire() {
+        let mut registry = Registry::default();
+        let busy = DecisionStep {
+            source: "busy".to_string(),
+            outcome: Outcome::TryAgain("server busy".to_string()),
+            reason: String::new(),
+        };
+        registry.register("busy", Fixed(vec![busy]));
+        let resolver = Resolver::with_bus(registry, SystemBus::default());
+        let sources = nsswitch::parse_sources("busy [TRYAGAIN=return] files");
+        let ctx = ResolveContext::new(None, None);
+
+        let mut steps = Vec::new();
+        let walked = resolver.walk(Database::Hosts, "db", &ctx, &sources, &mut steps, &Options::default()).await;
+        assert_eq!(walked.walk.spans.len(), 1);
+        assert!(matches!(walked.stop, Some((0, NssStatus::TryAgain, _))));
+        assert!(steps.iter().any(|step| step.source == "nsswitch (hosts)"));
+    }
+}
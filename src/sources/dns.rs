@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+
+use super::source::{Lookup, Query, Source};
+use crate::dns::{nameserver, resolved};
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::proc::{container, kubernetes};
+use crate::render::output::Database;
+
+/// nss-dns: systemd-resolved over D-Bus, falling back to libc.
+pub struct Dns;
+
+/// Queries every resolv.conf search expansion of `name` on its own, so an
+/// explanation can tell which expansion answered first. Returns false when
+/// there is nothing to expand or resolved cannot be reached.
+async fn expand_search(query: &Query<'_>, candidates: &mut Vec<Candidate>) -> bool {
+    let Ok(conf) = query.ctx.resolv_conf() else {
+        return false;
+    };
+    let queries = conf.search_candidates(query.name);
+    if queries.len() < 2 {
+        return false;
+    }
+    let Ok(connection) = query.bus.connection().await else {
+        return false;
+    };
+    for (position, expanded) in queries.iter().enumerate() {
+        // The trailing dot keeps resolved from applying its own search domains
+        let Ok(addresses) = resolved::resolve_addresses_via_resolved(connection, &format!("{}.", expanded)).await else {
+            continue;
+        };
+        candidates.extend(addresses.into_iter().map(|address| Candidate {
+            query: Some(expanded.clone()),
+            ..Candidate::new(&address, format!("search expansion {} of {}", position + 1, queries.len()))
+        }));
+    }
+    true
+}
+
+/// Why a container's resolv.conf points where it does.
+fn container_step(query: &Query<'_>) -> Option<DecisionStep> {
+    let ctx = query.ctx;
+    let info = ctx.container.as_ref()?;
+    let nameservers = ctx
+        .resolv_conf()
+        .map(|conf| conf.nameservers.clone())
+        .unwrap_or_default();
+    let explanation = container::explain_container_dns(info, &nameservers)?;
+    Some(DecisionStep {
+        source: format!("dns ({})", ctx.describe("/etc/resolv.conf")),
+        outcome: Outcome::Info(format!("nameservers {}", nameservers.join(", "))),
+        reason: explanation,
+    })
+}
+
+/// How cluster DNS expands the name through the pod's search list.
+fn cluster_step(query: &Query<'_>) -> Option<DecisionStep> {
+    let ctx = query.ctx;
+    let name = query.name;
+    let pod = ctx.pod.as_ref().filter(|pod| pod.dns_policy == kubernetes::DnsPolicy::ClusterFirst)?;
+    let conf = ctx.resolv_conf().ok()?;
+    let candidates: Vec<String> = conf
+        .search_candidates(name)
+        .iter()
+        .map(|candidate| format!("{} ({})", candidate, pod.interpret_name(candidate)))
+        .collect();
+    Some(DecisionStep {
+        source: format!("dns (cluster DNS {})", pod.cluster_dns.join(", ")),
+        outcome: Outcome::Info(format!("{} expands to {}", name, candidates.join(", then "))),
+        reason: format!(
+            "{} has {} dots and ndots is {}, so {}",
+            name,
+            name.matches('.').count(),
+            conf.ndots(),
+            if name.matches('.').count() >= conf.ndots() {
+                "it is tried as-is before the search list"
+            } else {
+                "every search domain is tried before the name as-is"
+            }
+        ),
+    })
+}
+
+/// What each resolv.conf nameserver answers for the name as given, one
+/// step per server. glibc stops at the first server that replies; these
+/// are informational and do not decide the lookup.
+async fn nameserver_steps(query: &Query<'_>) -> Vec<DecisionStep> {
+    let Ok(conf) = query.ctx.resolv_conf() else {
+        return Vec::new();
+    };
+    let timeout = std::time::Duration::from_secs(conf.timeout());
+    let mut steps = Vec::new();
+    for server in &conf.nameservers {
+        let source = format!("dns (nameserver {})", server);
+        let Some(address) = nameserver::nameserver_address(server) else {
+            steps.push(DecisionStep {
+                source,
+                outcome: Outcome::Info("not queried".to_string()),
+                reason: "Scoped link-local nameservers are not queried directly".to_string(),
+            });
+            continue;
+        };
+        let outcome = match nameserver::query_nameserver(address, query.name, timeout).await {
+            Ok(answer) if answer.addresses.is_empty() => Outcome::Info(nameserver::rcode_name(answer.rcode)),
+            Ok(answer) => Outcome::Info(format!(
+                "{}: {}",
+                nameserver::rcode_name(answer.rcode),
+                answer.addresses.iter().map(|address| address.to_string()).collect::<Vec<_>>().join(", ")
+            )),
+            Err(e) => Outcome::Error(e.to_string()),
+        };
+        steps.push(DecisionStep {
+            source,
+            outcome,
+            reason: format!("Queried {} directly for {} as given, without the search list", address, query.name),
+        });
+    }
+    steps
+}
+
+#[async_trait]
+impl Source for Dns {
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts]
+    }
+
+    fn live(&self) -> bool {
+        true
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        let name = query.name;
+        let mut lookup = Lookup::default();
+        lookup.steps.extend(container_step(query));
+        lookup.steps.extend(cluster_step(query));
+        if query.per_server {
+            lookup.steps.extend(nameserver_steps(query).await);
+        }
+
+        // Try systemd-resolved if Linux
+        let outcome = if cfg!(target_os = "linux") {
+            let addresses = match query.bus.connection().await {
+                Ok(connection) => resolved::resolve_addresses_via_resolved(connection, name).await,
+                Err(e) => Err(e.into()),
+            };
+            match addresses {
+                Ok(addresses) => {
+                    if !query.exhaustive || !expand_search(query, &mut lookup.candidates).await {
+                        lookup
+                            .candidates
+                            .extend(addresses.iter().map(|ip| Candidate::new(ip, "systemd-resolved".to_string())));
+                    }
+                    Outcome::Match(addresses.into_iter().next().unwrap_or_default())
+                }
+                Err(e) => Outcome::Error(format!("DBus error: {}", e)),
+            }
+        } else {
+            Outcome::Unsupported("systemd-resolved is Linux-only".to_string())
+        };
+        let matched = matches!(outcome, Outcome::Match(_));
+        lookup.steps.push(DecisionStep {
+            source: "dns (systemd-resolved)".to_string(),
+            outcome,
+            reason: if cfg!(target_os = "linux") { "Attempted systemd-resolved" } else { "Skipped on non-Linux" }.to_string(),
+        });
+        if matched {
+            return lookup;
+        }
+
+        // If not matched, try libc DNS
+        let addresses = resolved::resolve_addresses_libc(name).await;
+        for address in addresses.iter().flatten() {
+            lookup.candidates.push(Candidate {
+                filtered: address.is_ipv6().then(|| "the libc fallback only asks for IPv4 addresses".to_string()),
+                ..Candidate::new(&address.to_string(), "libc getaddrinfo".to_string())
+            });
+        }
+        let ipv4 = addresses.and_then(|addresses| {
+            addresses.into_iter().find(|a| a.is_ipv4()).map(|a| a.to_string()).ok_or_else(|| anyhow::anyhow!("No IPv4 address found"))
+        });
+        lookup.steps.push(match ipv4 {
+            Ok(ip) => DecisionStep {
+                source: "dns (libc)".to_string(),
+                outcome: Outcome::Match(ip),
+                reason: "Resolved using system resolver".to_string(),
+            },
+            Err(e) => DecisionStep {
+                source: "dns (libc)".to_string(),
+                outcome: Outcome::Error(format!("Libc error: {}", e)),
+                reason: "Failed to resolve via libc".to_string(),
+            },
+        });
+        lookup
+    }
+}
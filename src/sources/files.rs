@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+
+use super::source::{Lookup, Query, Source};
+use crate::context::ResolveContext;
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::nss::{group, hosts, passwd};
+use crate::render::output::{Database, Record};
+
+/// nss-files: /etc/hosts, /etc/passwd and /etc/group.
+pub struct Files;
+
+fn hosts(query: &Query<'_>) -> Lookup {
+    let ctx = query.ctx;
+    let entries = ctx.hosts().unwrap_or_default();
+    let matches = hosts::resolve_host_from_hosts(query.name, &entries);
+    let candidates = matches
+        .iter()
+        .map(|entry| Candidate::new(&entry.ip, format!("line {} of {}", entry.line, entry.source)))
+        .collect();
+    let step = match matches.first() {
+        Some(entry) => {
+            let alias = ctx.pod.as_ref().and_then(|pod| pod.host_alias(query.name));
+            DecisionStep {
+                source: format!("files ({})", ctx.describe("/etc/hosts")),
+                outcome: Outcome::Match(entry.ip.clone()),
+                reason: match alias {
+                    Some(alias) => format!("Found in hosts file (hostAliases entry for {} written by kubelet from the pod spec)", alias.ip),
+                    None => "Found in hosts file".to_string(),
+                },
+            }
+        }
+        None => DecisionStep {
+            source: format!("files ({})", ctx.describe("/etc/hosts")),
+            outcome: Outcome::NoMatch,
+            reason: "Not found in hosts file".to_string(),
+        },
+    };
+    Lookup {
+        steps: vec![step],
+        candidates,
+        record: hosts::getent_host_entry(query.name, &entries).map(Record::Host),
+    }
+}
+
+fn users(query: &Query<'_>) -> Lookup {
+    let ctx = query.ctx;
+    let users = ctx.passwd().unwrap_or_default();
+    match passwd::resolve_user_from_passwd(query.name, &users).first() {
+        Some(entry) => Lookup {
+            steps: vec![DecisionStep {
+                source: format!("files ({})", ctx.describe("/etc/passwd")),
+                outcome: Outcome::Match(entry.uid.to_string()),
+                reason: "Found in passwd file".to_string(),
+            }],
+            candidates: Vec::new(),
+            record: Some(Record::User((*entry).clone())),
+        },
+        None => Lookup {
+            steps: vec![DecisionStep {
+                source: format!("files ({})", ctx.describe("/etc/passwd")),
+                outcome: Outcome::NoMatch,
+                reason: "Not found in passwd file".to_string(),
+            }],
+            ..Lookup::default()
+        },
+    }
+}
+
+fn groups(query: &Query<'_>) -> Lookup {
+    let ctx = query.ctx;
+    let groups = ctx.group().unwrap_or_default();
+    match group::resolve_group_from_group(query.name, &groups).first() {
+        Some(entry) => Lookup {
+            steps: vec![DecisionStep {
+                source: format!("files ({})", ctx.describe("/etc/group")),
+                outcome: Outcome::Match(entry.gid.to_string()),
+                reason: "Found in group file".to_string(),
+            }],
+            candidates: Vec::new(),
+            record: Some(Record::Group((*entry).clone())),
+        },
+        None => Lookup {
+            steps: vec![DecisionStep {
+                source: format!("files ({})", ctx.describe("/etc/group")),
+                outcome: Outcome::NoMatch,
+                reason: "Not found in group file".to_string(),
+            }],
+            ..Lookup::default()
+        },
+    }
+}
+
+#[async_trait]
+impl Source for Files {
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts, Database::Passwd, Database::Group]
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        match query.database {
+            Database::Hosts => hosts(query),
+            Database::Passwd => users(query),
+            Database::Group => groups(query),
+        }
+    }
+
+    async fn enumerate(&self, database: Database, ctx: &ResolveContext) -> Option<Vec<Record>> {
+        let records = match database {
+            Database::Hosts => ctx.open("/etc/hosts").and_then(hosts::parse_hosts).ok()?.into_iter().map(Record::Host).collect(),
+            Database::Passwd => ctx.open("/etc/passwd").and_then(passwd::parse_passwd).ok()?.into_iter().map(Record::User).collect(),
+            Database::Group => ctx.open("/etc/group").and_then(group::parse_group).ok()?.into_iter().map(Record::Group).collect(),
+        };
+        Some(records)
+    }
+}
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+
+use super::source::{Lookup, Query, Source};
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::nss::libvirt;
+use crate::render::output::Database;
+
+/// nss-libvirt: DHCP leases of libvirt's dnsmasq, by hostname, or by domain
+/// name for `libvirt_guest`.
+pub struct Libvirt {
+    pub guest: bool,
+}
+
+#[async_trait]
+impl Source for Libvirt {
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts]
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        let (ctx, name, source) = (query.ctx, query.name, &query.source.name);
+        let now = ctx.now();
+        let step_source = format!("{} ({})", source, libvirt::DNSMASQ_DIR);
+        let loaded = libvirt::load_leases(ctx).and_then(|leases| {
+            let domains = if self.guest { libvirt::load_domains(ctx)? } else { Vec::new() };
+            Ok((leases, domains))
+        });
+        let (leases, domains) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                return Lookup {
+                    steps: vec![DecisionStep {
+                        source: step_source,
+                        outcome: Outcome::Error(format!("Cannot read dnsmasq status: {}", e)),
+                        reason: "libvirt's dnsmasq status files are unavailable".to_string(),
+                    }],
+                    ..Lookup::default()
+                }
+            }
+        };
+
+        let leases_found = if self.guest {
+            libvirt::leases_for_domain(name, &domains, &leases)
+        } else {
+            libvirt::leases_for_hostname(name, &leases)
+        };
+        let what = if self.guest { "domain" } else { "DHCP hostname" };
+        let Some(lease) = leases_found.iter().find(|lease| !lease.is_expired(now)) else {
+            let mut reason = format!("No active lease for {} {}", what, name);
+            for lease in &leases_found {
+                reason.push_str(&format!("; ignored {}", lease.describe(now)));
+            }
+            if self.guest && !domains.iter().any(|d| d.name == name) {
+                reason.push_str("; no domain of that name in any .macs file");
+            }
+            return Lookup {
+                steps: vec![DecisionStep {
+                    source: step_source,
+                    outcome: Outcome::NoMatch,
+                    reason,
+                }],
+                ..Lookup::default()
+            };
+        };
+        Lookup {
+            steps: vec![DecisionStep {
+                source: format!("{} (network {})", source, lease.network),
+                outcome: Outcome::Match(lease.ip.clone()),
+                reason: match domains.iter().find(|d| d.name == name) {
+                    Some(domain) => format!("Matched {} {} (MACs from {}): {}", what, name, domain.source, lease.describe(now)),
+                    None => format!("Matched {} {}: {}", what, name, lease.describe(now)),
+                },
+            }],
+            candidates: vec![Candidate::new(&lease.ip, format!("lease on network {}", lease.network))],
+            record: None,
+        }
+    }
+}
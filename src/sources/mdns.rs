@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+
+use super::source::{Lookup, Query, Source};
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::nss::mdns;
+use crate::render::output::Database;
+
+/// The nsswitch names nss-mdns installs.
+pub const MODULES: [&str; 6] = ["mdns", "mdns4", "mdns6", "mdns_minimal", "mdns4_minimal", "mdns6_minimal"];
+
+/// nss-mdns in each of its variants; the nsswitch name picks the address
+/// family and whether only `.local` names are handled.
+pub struct Mdns;
+
+#[async_trait]
+impl Source for Mdns {
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts]
+    }
+
+    fn live(&self) -> bool {
+        true
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        let (name, source) = (query.name, &query.source.name);
+        let step_source = format!("{} (multicast DNS)", source);
+        let Some(module) = mdns::MdnsModule::from_source(source) else {
+            return Lookup {
+                steps: vec![DecisionStep {
+                    source: step_source,
+                    outcome: Outcome::Unsupported(format!("{} is not an nss-mdns module", source)),
+                    reason: "glibc reports UNAVAIL when a module cannot be loaded".to_string(),
+                }],
+                ..Lookup::default()
+            };
+        };
+        let allow = query.ctx.open("/etc/mdns.allow").and_then(mdns::parse_mdns_allow).ok();
+        let why = match mdns::mdns_policy(&module, name, allow.as_deref()) {
+            mdns::MdnsPolicy::Refused(why) => {
+                return Lookup {
+                    steps: vec![DecisionStep {
+                        source: step_source,
+                        outcome: Outcome::Unsupported(why),
+                        reason: "nss-mdns returns UNAVAIL for names it does not handle".to_string(),
+                    }],
+                    ..Lookup::default()
+                }
+            }
+            mdns::MdnsPolicy::Allowed(why) => why,
+        };
+
+        let mut lookup = Lookup::default();
+        let outcome = match mdns::query_mdns(&module, name, mdns::MDNS_GROUP, mdns::MDNS_TIMEOUT).await {
+            Ok(addrs) if !addrs.is_empty() => {
+                lookup.candidates.extend(addrs.iter().map(|a| Candidate::new(&a.to_string(), "multicast DNS".to_string())));
+                Outcome::Match(addrs[0].to_string())
+            }
+            Ok(_) => Outcome::NoMatch,
+            Err(e) => Outcome::Error(format!("mDNS query failed: {}", e)),
+        };
+        lookup.steps.push(DecisionStep {
+            source: step_source,
+            outcome,
+            reason: format!("Queried {} ({})", mdns::MDNS_GROUP, why),
+        });
+        lookup
+    }
+}
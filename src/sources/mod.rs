@@ -0,0 +1,8 @@
+pub mod dns;
+pub mod files;
+pub mod libvirt;
+pub mod mdns;
+pub mod mymachines;
+pub mod plugin;
+pub mod registry;
+pub mod source;
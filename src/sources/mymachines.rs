@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+
+use super::source::{Lookup, Query, Source};
+use crate::dbus::{self, SystemBus};
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::nss::mymachines::{self, MachineIdKind};
+use crate::render::output::{Database, Record};
+
+/// nss-mymachines: addresses of machines registered with systemd-machined,
+/// and the users and groups it synthesizes for their mapped ids.
+pub struct Mymachines;
+
+const SOURCE: &str = "mymachines (systemd-machined)";
+
+async fn machine_addresses(name: &str, bus: &SystemBus) -> Lookup {
+    let addresses = match bus.connection().await {
+        Ok(connection) => mymachines::machine_addresses(connection, name).await,
+        Err(e) => Err(e),
+    };
+    let mut lookup = Lookup::default();
+    let step = match addresses {
+        Ok(addrs) if !addrs.is_empty() => {
+            let all: Vec<String> = addrs.iter().map(|a| a.to_string()).collect();
+            lookup.candidates.extend(all.iter().map(|a| Candidate::new(a, "systemd-machined".to_string())));
+            DecisionStep {
+                source: SOURCE.to_string(),
+                outcome: Outcome::Match(addrs[0].to_string()),
+                reason: format!("Machine {} is registered with systemd-machined; addresses: {}", name, all.join(", ")),
+            }
+        }
+        Ok(_) => DecisionStep {
+            source: SOURCE.to_string(),
+            outcome: Outcome::NoMatch,
+            reason: format!("Machine {} is registered but reports no addresses", name),
+        },
+        Err(e) if dbus::is_method_error(&e, mymachines::NO_SUCH_MACHINE) => {
+            let known = match bus.connection().await {
+                Ok(connection) => mymachines::list_machines(connection).await.unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            let known: Vec<String> = known.iter().map(|m| format!("{} ({}, {})", m.name, m.class, m.service)).collect();
+            DecisionStep {
+                source: SOURCE.to_string(),
+                outcome: Outcome::NoMatch,
+                reason: if known.is_empty() {
+                    "No machines are registered with systemd-machined".to_string()
+                } else {
+                    format!("Not a registered machine; known machines: {}", known.join(", "))
+                },
+            }
+        }
+        Err(e) => DecisionStep {
+            source: SOURCE.to_string(),
+            outcome: Outcome::Error(format!("DBus error: {}", e)),
+            reason: "Cannot reach org.freedesktop.machine1".to_string(),
+        },
+    };
+    lookup.steps.push(step);
+    lookup
+}
+
+async fn machine_id(name: &str, kind: MachineIdKind, bus: &SystemBus) -> DecisionStep {
+    let (id_label, pattern, no_mapping) = match kind {
+        MachineIdKind::User => ("uid", "vu-<machine>-<uid>", mymachines::NO_SUCH_USER_MAPPING),
+        MachineIdKind::Group => ("gid", "vg-<machine>-<gid>", mymachines::NO_SUCH_GROUP_MAPPING),
+    };
+    let source = SOURCE.to_string();
+
+    let Some((machine, id)) = mymachines::parse_machine_id_name(name, kind) else {
+        return DecisionStep {
+            source,
+            outcome: Outcome::NoMatch,
+            reason: format!("Not a {} name; nss-mymachines only synthesizes container ids", pattern),
+        };
+    };
+    let connection = match bus.connection().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            return DecisionStep {
+                source,
+                outcome: Outcome::Error(format!("DBus error: {}", e)),
+                reason: "Cannot reach org.freedesktop.machine1".to_string(),
+            }
+        }
+    };
+
+    match mymachines::map_from_machine(connection, &machine, id, kind).await {
+        Ok(host_id) => {
+            let shift = match mymachines::machine_id_shift(connection, &machine, kind).await {
+                Ok(shift) => {
+                    let ranges: Vec<String> = shift.ranges.iter().map(|r| r.describe()).collect();
+                    format!(" ({} {}, leader pid {}, {} shift {})", shift.class, machine, shift.leader, id_label, ranges.join(", "))
+                }
+                Err(_) => String::new(),
+            };
+            DecisionStep {
+                source,
+                outcome: Outcome::Match(host_id.to_string()),
+                reason: format!("Container {} {} of machine {} maps to host {} {}{}", id_label, id, machine, id_label, host_id, shift),
+            }
+        }
+        Err(e) if dbus::is_method_error(&e, mymachines::NO_SUCH_MACHINE) => DecisionStep {
+            source,
+            outcome: Outcome::NoMatch,
+            reason: format!("No machine named {} is registered with systemd-machined", machine),
+        },
+        Err(e) if dbus::is_method_error(&e, no_mapping) => DecisionStep {
+            source,
+            outcome: Outcome::NoMatch,
+            reason: format!("Machine {} has no mapping for container {} {}", machine, id_label, id),
+        },
+        Err(e) => DecisionStep {
+            source,
+            outcome: Outcome::Error(format!("DBus error: {}", e)),
+            reason: "systemd-machined call failed".to_string(),
+        },
+    }
+}
+
+#[async_trait]
+impl Source for Mymachines {
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts, Database::Passwd, Database::Group]
+    }
+
+    fn live(&self) -> bool {
+        true
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        let kind = match query.database {
+            Database::Hosts => return machine_addresses(query.name, query.bus).await,
+            Database::Passwd => MachineIdKind::User,
+            Database::Group => MachineIdKind::Group,
+        };
+        let step = machine_id(query.name, kind, query.bus).await;
+        let record = match &step.outcome {
+            Outcome::Match(host_id) => {
+                let host_id = host_id.parse().unwrap_or_default();
+                Some(match kind {
+                    MachineIdKind::User => Record::User(mymachines::synthesized_user(query.name, host_id)),
+                    MachineIdKind::Group => Record::Group(mymachines::synthesized_group(query.name, host_id)),
+                })
+            }
+            _ => None,
+        };
+        Lookup {
+            steps: vec![step],
+            candidates: Vec::new(),
+            record,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::{dbus_interface, Connection, ConnectionBuilder, DBusError, Guid};
+
+    use super::*;
+    use crate::context::ResolveContext;
+    use crate::nss::nsswitch::NssSource;
+    use crate::resolver::Resolver;
+    use crate::sources::registry::Registry;
+
+    #[derive(Debug, DBusError)]
+    #[dbus_error(prefix = "org.freedesktop.machine1")]
+    enum MachineError {
+        #[dbus_error(zbus_error)]
+        ZBus(zbus::Error),
+        NoSuchMachine(String),
+        NoSuchUserMapping(String),
+    }
+
+    /// Stands in for systemd-machined with one container, `web`, whose
+    /// uids and gids are shifted by 100000.
+    struct Manager;
+
+    fn machine(name: &str) -> Result<(), MachineError> {
+        match name {
+            "web" => Ok(()),
+            _ => Err(MachineError::NoSuchMachine(format!("No machine '{}' known", name))),
+        }
+    }
+
+    #[dbus_interface(name = "org.freedesktop.machine1.Manager")]
+    impl Manager {
+        fn list_machines(&self) -> Vec<(String, String, String, OwnedObjectPath)> {
+            let path = OwnedObjectPath::try_from("/org/freedesktop/machine1/machine/web").unwrap();
+            vec![("web".to_string(), "container".to_string(), "systemd-nspawn".to_string(), path)]
+        }
+
+        fn get_machine(&self, name: &str) -> Result<OwnedObjectPath, MachineError> {
+            machine(name)?;
+            Ok(OwnedObjectPath::try_from("/org/freedesktop/machine1/machine/web").unwrap())
+        }
+
+        fn get_machine_addresses(&self, name: &str) -> Result<Vec<(i32, Vec<u8>)>, MachineError> {
+            machine(name)?;
+            // AF_INET and AF_INET6
+            Ok(vec![(2, vec![10, 0, 3, 7]), (10, vec![0xfd; 16])])
+        }
+
+        fn map_from_machine_user(&self, name: &str, uid: u32) -> Result<u32, MachineError> {
+            machine(name)?;
+            match uid {
+                0..=65535 => Ok(100000 + uid),
+                _ => Err(MachineError::NoSuchUserMapping(format!("uid {} is not mapped", uid))),
+            }
+        }
+
+        fn map_from_machine_group(&self, name: &str, gid: u32) -> Result<u32, MachineError> {
+            machine(name)?;
+            Ok(100000 + gid)
+        }
+    }
+
+    struct Machine;
+
+    #[dbus_interface(name = "org.freedesktop.machine1.Machine")]
+    impl Machine {
+        #[dbus_interface(property)]
+        fn leader(&self) -> u32 {
+            std::process::id()
+        }
+
+        #[dbus_interface(property)]
+        fn class(&self) -> String {
+            "container".to_string()
+        }
+    }
+
+    /// A resolver whose bus is a peer-to-peer connection to the stand-in;
+    /// the server end is returned to keep it alive.
+    async fn resolver() -> (Resolver, Connection) {
+        let (server, client) = UnixStream::pair().unwrap();
+        let guid = Guid::generate();
+        let server = ConnectionBuilder::unix_stream(server)
+            .server(&guid)
+            .p2p()
+            .serve_at("/org/freedesktop/machine1", Manager)
+            .unwrap()
+            .serve_at("/org/freedesktop/machine1/machine/web", Machine)
+            .unwrap()
+            .build();
+        let client = ConnectionBuilder::unix_stream(client).p2p().build();
+        let (server, client) = tokio::try_join!(server, client).unwrap();
+        (Resolver::with_bus(Registry::builtin(), SystemBus::with_connection(client)), server)
+    }
+
+    async fn lookup(resolver: &Resolver, database: Database, name: &str) -> Lookup {
+        let source = NssSource::new("mymachines");
+        let query = Query {
+            database,
+            name,
+            source: &source,
+            ctx: &ResolveContext::new(None, None),
+            bus: resolver.bus(),
+            exhaustive: false,
+            per_server: false,
+        };
+        Mymachines.lookup(&query).await
+    }
+
+    #[tokio::test]
+    async fn machine_addresses_come_from_machined() {
+        let (resolver, _server) = resolver().await;
+        let found = lookup(&resolver, Database::Hosts, "web").await;
+        assert!(matches!(&found.steps[0].outcome, Outcome::Match(address) if address == "10.0.3.7"));
+        assert_eq!(found.candidates.len(), 2);
+
+        let missing = lookup(&resolver, Database::Hosts, "db").await;
+        assert!(matches!(missing.steps[0].outcome, Outcome::NoMatch));
+        assert!(missing.steps[0].reason.contains("web (container, systemd-nspawn)"), "{}", missing.steps[0].reason);
+    }
+
+    #[tokio::test]
+    async fn container_ids_map_to_host_ids() {
+        let (resolver, _server) = resolver().await;
+        let user = lookup(&resolver, Database::Passwd, "vu-web-1000").await;
+        assert!(matches!(&user.steps[0].outcome, Outcome::Match(uid) if uid == "101000"));
+        assert!(user.steps[0].reason.contains("leader pid"), "{}", user.steps[0].reason);
+        assert!(matches!(user.record, Some(Record::User(entry)) if entry.uid == 101000 && entry.name == "vu-web-1000"));
+
+        let group = lookup(&resolver, Database::Group, "vg-web-5").await;
+        assert!(matches!(&group.steps[0].outcome, Outcome::Match(gid) if gid == "100005"));
+
+        let unmapped = lookup(&resolver, Database::Passwd, "vu-web-70000").await;
+        assert!(matches!(unmapped.steps[0].outcome, Outcome::NoMatch));
+        assert!(unmapped.steps[0].reason.contains("no mapping"), "{}", unmapped.steps[0].reason);
+
+        let no_machine = lookup(&resolver, Database::Passwd, "vu-db-0").await;
+        assert!(no_machine.steps[0].reason.contains("No machine named db"), "{}", no_machine.steps[0].reason);
+
+        let plain = lookup(&resolver, Database::Passwd, "alice").await;
+        assert!(matches!(plain.steps[0].outcome, Outcome::NoMatch));
+    }
+}
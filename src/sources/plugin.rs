@@ -0,0 +1,339 @@
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use super::source::{Lookup, Query, Source};
+use crate::explain::decision_tree::{DecisionStep, Outcome};
+use crate::explain::why_not::Candidate;
+use crate::nss::nsswitch::NssStatus;
+use crate::nss::{group, passwd};
+use crate::render::output::{Database, Record};
+use crate::render::schema::NodeOutcome;
+
+/// Version of the request line a plugin is sent.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long a plugin has to answer before it is killed and reported as UNAVAIL.
+pub const PLUGIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How much of a plugin's stderr is kept for error messages; the rest is
+/// read and discarded so the plugin never blocks on a full pipe.
+const STDERR_KEPT: usize = 4096;
+
+/// Executables are named `resolve-source-<nsswitch name>`.
+pub const PLUGIN_PREFIX: &str = "resolve-source-";
+
+/// The JSON line written to a plugin's stdin.
+#[derive(Serialize)]
+pub struct Request<'a> {
+    pub version: u32,
+    pub database: Database,
+    /// The name looked up: a host name in ASCII form, or a user or group name.
+    pub key: &'a str,
+    pub context: RequestContext<'a>,
+}
+
+/// Whose view of the system the lookup is for.
+#[derive(Serialize)]
+pub struct RequestContext<'a> {
+    /// "local system", "pid 42 (container web)" and so on.
+    pub target: String,
+    pub pid: Option<u32>,
+    /// The `ip netns` namespace, with `--netns`.
+    pub netns: Option<&'a str>,
+}
+
+/// The JSON line a plugin answers with on stdout.
+#[derive(Deserialize)]
+pub struct Response {
+    pub status: NssStatus,
+    /// For hosts, IP addresses; for passwd and group, lines in the format of
+    /// /etc/passwd and /etc/group. The first entry is the answer.
+    #[serde(default)]
+    pub entries: Vec<String>,
+    /// How the plugin reached its answer, shown before its status.
+    #[serde(default)]
+    pub steps: Vec<ResponseStep>,
+}
+
+#[derive(Deserialize)]
+pub struct ResponseStep {
+    /// Defaults to `<name> (plugin)`.
+    #[serde(default)]
+    pub source: Option<String>,
+    pub outcome: NodeOutcome,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// An NSS source implemented by an external executable speaking the
+/// line-delimited JSON protocol: one [`Request`] line in, one [`Response`]
+/// line out, one process per lookup.
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Whether `name` can be part of an executable name. nsswitch.conf can come
+/// from an image or a container, so names that reach outside the plugin
+/// directories are never looked up.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The first `resolve-source-<name>` executable in `dirs`.
+pub fn find_plugin(name: &str, dirs: &[PathBuf]) -> Option<Plugin> {
+    if !valid_name(name) {
+        return None;
+    }
+    dirs.iter().map(|dir| dir.join(format!("{}{}", PLUGIN_PREFIX, name))).find_map(|path| {
+        is_executable(&path).then(|| Plugin {
+            name: name.to_string(),
+            path,
+        })
+    })
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+impl Plugin {
+    /// Runs the plugin for one request and reads its response line.
+    async fn call(&self, request: &Request<'_>) -> io::Result<Response> {
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stderr = child.stderr.take().map(|mut pipe| {
+            tokio::spawn(async move {
+                let mut kept = Vec::new();
+                let mut buf = [0u8; 8192];
+                while let Ok(read) = pipe.read(&mut buf).await {
+                    if read == 0 {
+                        break;
+                    }
+                    let room = STDERR_KEPT.saturating_sub(kept.len());
+                    kept.extend_from_slice(&buf[..read.min(room)]);
+                }
+                String::from_utf8_lossy(&kept).to_string()
+            })
+        });
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        let mut stdin = child.stdin.take().ok_or_else(|| io::Error::other("no stdin"))?;
+        stdin.write_all(line.as_bytes()).await?;
+        drop(stdin);
+
+        let mut stdout = BufReader::new(child.stdout.take().ok_or_else(|| io::Error::other("no stdout"))?);
+        let mut response = String::new();
+        let read = tokio::time::timeout(PLUGIN_TIMEOUT, stdout.read_line(&mut response)).await;
+        let read = read.map_err(|_| io::Error::new(io::ErrorKind::TimedOut, format!("no response within {}s", PLUGIN_TIMEOUT.as_secs())))?;
+        if read? == 0 {
+            let stderr = match stderr {
+                Some(drain) => tokio::time::timeout(Duration::from_millis(200), drain).await.ok().and_then(Result::ok).unwrap_or_default(),
+                None => String::new(),
+            };
+            let status = tokio::time::timeout(Duration::from_millis(200), child.wait()).await;
+            let exited = match status {
+                Ok(Ok(status)) => format!("exited with {}", status),
+                _ => "closed stdout".to_string(),
+            };
+            return Err(match stderr.lines().next().filter(|line| !line.trim().is_empty()) {
+                Some(message) => io::Error::other(format!("{} without a response: {}", exited, message.trim())),
+                None => io::Error::other(format!("{} without a response", exited)),
+            });
+        }
+        serde_json::from_str(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("malformed response: {}", e)))
+    }
+}
+
+/// The entry of a passwd or group line a plugin returned, with its id.
+/// Unlike /etc/passwd, where glibc reads an empty id as 0, every id field
+/// must be a number.
+fn parse_entry(database: Database, line: &str, origin: String) -> Option<(String, Record)> {
+    let ids = match database {
+        Database::Hosts => return None,
+        Database::Passwd => 2..4,
+        Database::Group => 2..3,
+    };
+    let fields: Vec<&str> = line.split(':').collect();
+    if !fields.get(ids)?.iter().all(|field| !field.is_empty() && passwd::parse_id(field).is_some()) {
+        return None;
+    }
+    let reader = io::Cursor::new(line.as_bytes());
+    match database {
+        Database::Hosts => None,
+        Database::Passwd => {
+            let entry = passwd::parse_passwd(reader).ok()?.into_iter().next()?;
+            Some((entry.uid.to_string(), Record::User(passwd::UserEntry { source: origin, ..entry })))
+        }
+        Database::Group => {
+            let entry = group::parse_group(reader).ok()?.into_iter().next()?;
+            Some((entry.gid.to_string(), Record::Group(group::GroupEntry { source: origin, ..entry })))
+        }
+    }
+}
+
+#[async_trait]
+impl Source for Plugin {
+    /// Every database; a plugin that does not serve one answers UNAVAIL.
+    fn databases(&self) -> &[Database] {
+        &[Database::Hosts, Database::Passwd, Database::Group]
+    }
+
+    /// Plugins typically talk to directory servers, so offline targets
+    /// replay their answers rather than run them.
+    fn live(&self) -> bool {
+        true
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup {
+        let ctx = query.ctx;
+        let request = Request {
+            version: PROTOCOL_VERSION,
+            database: query.database,
+            key: query.name,
+            context: RequestContext {
+                target: ctx.label(),
+                pid: ctx.pid,
+                netns: ctx.netns.as_ref().map(|netns| netns.name.as_str()),
+            },
+        };
+        let source = format!("{} (plugin)", self.name);
+        let response = match self.call(&request).await {
+            Ok(response) => response,
+            Err(e) => {
+                return Lookup {
+                    steps: vec![DecisionStep {
+                        source,
+                        outcome: Outcome::Error(format!("Plugin failed: {}", e)),
+                        reason: format!("{} did not answer; treated as UNAVAIL", self.path.display()),
+                    }],
+                    ..Lookup::default()
+                }
+            }
+        };
+
+        let mut lookup = Lookup::default();
+        lookup.steps.extend(response.steps.into_iter().map(|step| DecisionStep {
+            source: step.source.unwrap_or_else(|| source.clone()),
+            outcome: step.outcome.into(),
+            reason: step.reason,
+        }));
+        let answered = format!("{} returned {}", self.path.display(), response.status.as_str());
+        let outcome = match response.status {
+            NssStatus::Success => match (query.database, response.entries.first()) {
+                (_, None) => Outcome::Error("SUCCESS without entries".to_string()),
+                (Database::Hosts, Some(address)) => match response.entries.iter().find(|entry| entry.parse::<IpAddr>().is_err()) {
+                    Some(entry) => Outcome::Error(format!("malformed hosts entry: {}", entry)),
+                    None => {
+                        lookup.candidates.extend(response.entries.iter().map(|address| Candidate::new(address, format!("plugin {}", self.name))));
+                        Outcome::Match(address.clone())
+                    }
+                },
+                (database, Some(line)) => match parse_entry(database, line, format!("plugin {}", self.name)) {
+                    Some((id, record)) => {
+                        lookup.record = Some(record);
+                        Outcome::Match(id)
+                    }
+                    None => Outcome::Error(format!("malformed {} entry: {}", database.as_str(), line)),
+                },
+            },
+            NssStatus::NotFound => Outcome::NoMatch,
+            NssStatus::Unavail => Outcome::Unsupported("Plugin reported UNAVAIL".to_string()),
+            NssStatus::TryAgain => Outcome::TryAgain("Plugin reported TRYAGAIN".to_string()),
+        };
+        let reason = match &outcome {
+            Outcome::Error(_) => format!("{}, which is not a valid answer; treated as UNAVAIL", answered),
+            _ => answered,
+        };
+        lookup.steps.push(DecisionStep { source, outcome, reason });
+        lookup
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+    use crate::context::ResolveContext;
+    use crate::nss::nsswitch::NssSource;
+
+    #[test]
+    fn entries_need_numeric_ids() {
+        let user = parse_entry(Database::Passwd, "deploy:x:1500:1500::/home/deploy:/bin/sh", String::new());
+        assert!(matches!(user, Some((uid, Record::User(_))) if uid == "1500"));
+        for line in ["deploy:x:abc:1500::/home/deploy:/bin/sh", "deploy:x::1500::/home/deploy:/bin/sh", "deploy:x:1500:+1::/:/bin/sh", "deploy:x:1500"] {
+            assert!(parse_entry(Database::Passwd, line, String::new()).is_none(), "{}", line);
+        }
+        assert!(matches!(parse_entry(Database::Group, "docker:x:998:deploy", String::new()), Some((gid, _)) if gid == "998"));
+        assert!(parse_entry(Database::Group, "docker:x::deploy", String::new()).is_none());
+    }
+
+    /// A plugin directory holding `resolve-source-<name>` running `script`.
+    fn plugin_dir(name: &str, script: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("resolve-plugin-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}{}", PLUGIN_PREFIX, name));
+        std::fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    /// Looks `key` up through the plugin `name` that runs `script`.
+    async fn lookup(name: &str, script: &str, database: Database, key: &str) -> Lookup {
+        let dir = plugin_dir(name, script);
+        let plugin = find_plugin(name, std::slice::from_ref(&dir)).unwrap();
+        let source = NssSource::new(name);
+        let query = Query {
+            database,
+            name: key,
+            source: &source,
+            ctx: &ResolveContext::new(None, None),
+            bus: &Default::default(),
+            exhaustive: false,
+            per_server: false,
+        };
+        let lookup = plugin.lookup(&query).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        lookup
+    }
+
+    #[tokio::test]
+    async fn tryagain_is_kept() {
+        let lookup = lookup("busy", "cat >/dev/null\necho '{\"status\":\"TRYAGAIN\"}'\n", Database::Passwd, "root").await;
+        let step = lookup.steps.last().unwrap();
+        assert!(matches!(step.outcome, Outcome::TryAgain(_)), "{:?}", step.outcome);
+        assert_eq!(NssStatus::from_outcome(&step.outcome), Some(NssStatus::TryAgain));
+    }
+
+    #[tokio::test]
+    async fn chatty_stderr_does_not_block() {
+        // Well past the 64 KiB a pipe buffers, written before the response
+        let script = "head -c 262144 /dev/zero | tr '\\0' x >&2\ncat >/dev/null\necho '{\"status\":\"SUCCESS\",\"entries\":[\"10.0.0.5\"]}'\n";
+        let lookup = lookup("chatty", script, Database::Hosts, "db").await;
+        let step = lookup.steps.last().unwrap();
+        assert!(matches!(&step.outcome, Outcome::Match(address) if address == "10.0.0.5"), "{:?}", step.outcome);
+    }
+
+    #[tokio::test]
+    async fn hosts_entries_must_be_addresses() {
+        let script = "cat >/dev/null\necho '{\"status\":\"SUCCESS\",\"entries\":[\"10.0.0.5\",\"db.internal\"]}'\n";
+        let lookup = lookup("names", script, Database::Hosts, "db").await;
+        let step = lookup.steps.last().unwrap();
+        assert!(matches!(&step.outcome, Outcome::Error(e) if e == "malformed hosts entry: db.internal"), "{:?}", step.outcome);
+        assert!(lookup.candidates.is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::source::Source;
+use super::{dns, files, libvirt, mdns, mymachines, plugin};
+
+/// Maps nsswitch service names to the sources that implement them, falling
+/// back to `resolve-source-<name>` plugins in the plugin directories.
+#[derive(Clone, Default)]
+pub struct Registry {
+    sources: HashMap<String, Arc<dyn Source>>,
+    plugin_dirs: Vec<PathBuf>,
+}
+
+impl Registry {
+    /// The sources built into resolve.
+    pub fn builtin() -> Self {
+        let mut registry = Registry::default();
+        registry.register("files", files::Files);
+        registry.register("dns", dns::Dns);
+        registry.register("libvirt", libvirt::Libvirt { guest: false });
+        registry.register("libvirt_guest", libvirt::Libvirt { guest: true });
+        registry.register("mymachines", mymachines::Mymachines);
+        for name in mdns::MODULES {
+            registry.register(name, mdns::Mdns);
+        }
+        registry
+    }
+
+    /// Registers `source` under `name`, replacing any source of that name.
+    pub fn register(&mut self, name: &str, source: impl Source + 'static) {
+        self.sources.insert(name.to_string(), Arc::new(source));
+    }
+
+    /// Searches `dir` for plugins after the directories added before it.
+    pub fn add_plugin_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.plugin_dirs.push(dir.into());
+    }
+
+    pub fn plugin_dirs(&self) -> &[PathBuf] {
+        &self.plugin_dirs
+    }
+
+    /// The registered source of that name, or else the first plugin for it.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Source>> {
+        if let Some(source) = self.sources.get(name) {
+            return Some(source.clone());
+        }
+        plugin::find_plugin(name, &self.plugin_dirs).map(|plugin| Arc::new(plugin) as Arc<dyn Source>)
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+
+use crate::context::ResolveContext;
+use crate::dbus::SystemBus;
+use crate::explain::decision_tree::DecisionStep;
+use crate::explain::why_not::Candidate;
+use crate::nss::nsswitch::NssSource;
+use crate::render::output::{Database, Record};
+
+/// One lookup of a key in one nsswitch source.
+pub struct Query<'a> {
+    pub database: Database,
+    /// A host name in its ASCII form, or a user or group name.
+    pub name: &'a str,
+    /// The nsswitch entry being consulted, e.g. `mdns4_minimal`.
+    pub source: &'a NssSource,
+    pub ctx: &'a ResolveContext,
+    pub bus: &'a SystemBus,
+    /// Every address the source could return is wanted, for `--why-not`.
+    pub exhaustive: bool,
+    /// Every nameserver's own answer is wanted, for graphs.
+    pub per_server: bool,
+}
+
+/// What a source did with a query.
+#[derive(Debug, Default)]
+pub struct Lookup {
+    /// The decisive step comes last: its outcome is the NSS status the
+    /// walk acts on.
+    pub steps: Vec<DecisionStep>,
+    /// Addresses a hosts source saw, including those it drops. The
+    /// resolver fills in their source and position.
+    pub candidates: Vec<Candidate>,
+    /// The whole entry, when a passwd or group source provides one.
+    pub record: Option<Record>,
+}
+
+/// An NSS service such as `files` or `dns`. Register implementations in a
+/// [`super::registry::Registry`] under the name nsswitch.conf uses.
+#[async_trait]
+pub trait Source: Send + Sync {
+    /// The databases the source serves. Like glibc with a module that lacks
+    /// the function, the walk reports UNAVAIL for the others.
+    fn databases(&self) -> &[Database];
+
+    /// Whether the source answers from sockets or D-Bus rather than from
+    /// files. Offline targets report such sources as UNAVAIL, or replay the
+    /// answers a snapshot recorded.
+    fn live(&self) -> bool {
+        false
+    }
+
+    async fn lookup(&self, query: &Query<'_>) -> Lookup;
+
+    /// Every entry of `database`, for sources that can list them as
+    /// `getent` without a key does. `None` when the source cannot.
+    async fn enumerate(&self, _database: Database, _ctx: &ResolveContext) -> Option<Vec<Record>> {
+        None
+    }
+}
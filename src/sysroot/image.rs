@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::memory::{self, Entry, MemoryFs, Node};
+use super::{Backing, Sysroot};
+
+/// Files larger than this keep only their metadata; nothing resolution
+/// reads from /etc comes close.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+const MEDIA_TYPE_OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const MEDIA_TYPE_DOCKER_LIST: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+
+#[derive(Debug, Deserialize)]
+struct Descriptor {
+    digest: String,
+    platform: Option<Platform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    architecture: String,
+    os: String,
+}
+
+/// Both image indexes and image manifests; which fields are present tells
+/// them apart when `mediaType` is missing.
+#[derive(Debug, Deserialize)]
+struct OciDocument {
+    #[serde(rename = "mediaType", default)]
+    media_type: String,
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+    config: Option<Descriptor>,
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+}
+
+/// One entry of the `manifest.json` written by `docker save`.
+#[derive(Debug, Deserialize)]
+struct DockerSaveManifest {
+    #[serde(rename = "Config")]
+    config: String,
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageConfig {
+    config: Option<ImageRuntimeConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageRuntimeConfig {
+    #[serde(rename = "User", default)]
+    user: String,
+}
+
+/// Where the image's files (blobs, manifests) live: an unpacked OCI layout
+/// directory or a tar archive, indexed by member name.
+enum Store {
+    Dir(PathBuf),
+    Tar { path: PathBuf, members: HashMap<String, (u64, u64)> },
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn member_name(name: &str) -> String {
+    memory::normalize(name)
+}
+
+/// Member names come from the image's own manifests, so they must stay
+/// inside the layout directory or archive.
+fn check_member(name: &str) -> io::Result<()> {
+    if name.starts_with('/') || name.split('/').any(|part| part == "..") {
+        return Err(invalid(format!("{} points outside the image", name)));
+    }
+    Ok(())
+}
+
+impl Store {
+    fn open_tar(path: &Path) -> io::Result<Store> {
+        let mut archive = tar::Archive::new(fs::File::open(path)?);
+        let mut members = HashMap::new();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = member_name(&entry.path()?.to_string_lossy());
+            members.insert(name, (entry.raw_file_position(), entry.size()));
+        }
+        Ok(Store::Tar {
+            path: path.to_path_buf(),
+            members,
+        })
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        match self {
+            Store::Dir(dir) => dir.join(name).is_file(),
+            Store::Tar { members, .. } => members.contains_key(&member_name(name)),
+        }
+    }
+
+    fn open(&self, name: &str) -> io::Result<Box<dyn Read>> {
+        check_member(name)?;
+        match self {
+            Store::Dir(dir) => Ok(Box::new(fs::File::open(dir.join(name))?)),
+            Store::Tar { path, members } => {
+                let &(offset, size) = members
+                    .get(&member_name(name))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is not in the archive", name)))?;
+                let mut file = fs::File::open(path)?;
+                file.seek(SeekFrom::Start(offset))?;
+                Ok(Box::new(file.take(size)))
+            }
+        }
+    }
+
+    fn read_json<T: for<'de> Deserialize<'de>>(&self, name: &str) -> io::Result<T> {
+        let mut content = Vec::new();
+        self.open(name)?.read_to_end(&mut content)?;
+        serde_json::from_slice(&content).map_err(|e| invalid(format!("{}: {}", name, e)))
+    }
+}
+
+/// Maps an `algorithm:hex` digest to its blob, rejecting anything else so a
+/// crafted digest cannot name a path outside `blobs/`.
+fn blob_path(digest: &str) -> io::Result<String> {
+    let malformed = || invalid(format!("malformed digest {}", digest));
+    let (algorithm, hex) = digest.split_once(':').ok_or_else(malformed)?;
+    let algorithm_ok = !algorithm.is_empty()
+        && algorithm
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '.' | '_' | '-'))
+        && !algorithm.starts_with('.');
+    let hex_ok = !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit());
+    if !algorithm_ok || !hex_ok {
+        return Err(malformed());
+    }
+    Ok(format!("blobs/{}/{}", algorithm, hex))
+}
+
+fn go_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "powerpc64" => "ppc64le",
+        other => other,
+    }
+}
+
+/// Picks the manifest for this machine from an index, as `docker pull`
+/// would. Attestation manifests carry an `unknown/unknown` platform.
+fn select_manifest(manifests: &[Descriptor]) -> Option<&Descriptor> {
+    manifests
+        .iter()
+        .find(|m| m.platform.as_ref().is_some_and(|p| p.os == "linux" && p.architecture == go_arch()))
+        .or_else(|| manifests.iter().find(|m| m.platform.as_ref().is_none_or(|p| p.os != "unknown")))
+}
+
+/// Follows an OCI `index.json` down to an image manifest, returning the
+/// config and layer blob paths.
+fn oci_layers(store: &Store) -> io::Result<(Option<String>, Vec<String>)> {
+    let mut document: OciDocument = store.read_json("index.json")?;
+    // Nested indexes (multi-platform images) are followed a few levels deep
+    for _ in 0..4 {
+        let is_index = document.media_type == MEDIA_TYPE_OCI_INDEX
+            || document.media_type == MEDIA_TYPE_DOCKER_LIST
+            || (document.config.is_none() && !document.manifests.is_empty());
+        if !is_index {
+            let config = document.config.as_ref().map(|c| blob_path(&c.digest)).transpose()?;
+            let layers = document.layers.iter().map(|l| blob_path(&l.digest)).collect::<io::Result<_>>()?;
+            return Ok((config, layers));
+        }
+        let manifest = select_manifest(&document.manifests)
+            .ok_or_else(|| invalid(format!("no linux/{} manifest in the image index", go_arch())))?;
+        document = store.read_json(&blob_path(&manifest.digest)?)?;
+    }
+    Err(invalid("image index nests too deeply".to_string()))
+}
+
+/// Opens a layer blob, recognizing compression by its magic bytes since
+/// `docker save` does not record media types for its layers.
+fn open_layer(store: &Store, name: &str) -> io::Result<Box<dyn Read>> {
+    let mut reader = io::BufReader::new(store.open(name)?);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(flate2::read::GzDecoder::new(reader)))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Err(invalid(format!("{} is zstd-compressed, which is not supported", name)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+enum Change {
+    Insert(String, Entry),
+    HardLink(String, String),
+}
+
+/// Applies one layer changeset. Whiteouts only hide files from lower layers,
+/// so they are applied before anything the layer itself adds.
+fn apply_layer(root: &mut MemoryFs, layer: impl Read) -> io::Result<()> {
+    let mut archive = tar::Archive::new(layer);
+    let mut changes = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = memory::normalize(&entry.path()?.to_string_lossy());
+        let (dir, file_name) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+
+        if file_name == OPAQUE_WHITEOUT {
+            root.clear_dir(dir);
+            continue;
+        }
+        if let Some(hidden) = file_name.strip_prefix(WHITEOUT_PREFIX) {
+            root.remove_tree(&format!("{}/{}", dir, hidden));
+            continue;
+        }
+
+        let header = entry.header();
+        let mode = header.mode().unwrap_or(0o644);
+        let uid = header.uid().unwrap_or(0) as u32;
+        let gid = header.gid().unwrap_or(0) as u32;
+        let entry_type = header.entry_type();
+        let link = entry.link_name()?.map(|l| l.to_string_lossy().to_string());
+
+        let node = if entry_type.is_dir() {
+            Node::Dir
+        } else if entry_type.is_symlink() {
+            Node::Symlink(link.unwrap_or_default())
+        } else if entry_type.is_hard_link() {
+            changes.push(Change::HardLink(path, memory::normalize(&link.unwrap_or_default())));
+            continue;
+        } else if entry_type.is_file() {
+            let size = entry.size();
+            let data = if size <= MAX_FILE_SIZE {
+                let mut data = Vec::with_capacity(size as usize);
+                entry.read_to_end(&mut data)?;
+                Some(data)
+            } else {
+                None
+            };
+            Node::File { data, size }
+        } else {
+            // Devices and fifos have nothing for resolution to read
+            continue;
+        };
+        changes.push(Change::Insert(path, Entry { node, mode, uid, gid }));
+    }
+
+    for change in changes {
+        match change {
+            Change::Insert(path, entry) => root.insert(&path, entry),
+            Change::HardLink(path, target) => {
+                if let Some(entry) = root.entry(&target).cloned() {
+                    root.insert(&path, entry);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads a container image and flattens its layers into a [`Sysroot`].
+/// `path` may be an OCI image layout directory, an OCI archive, or a tarball
+/// written by `docker save`.
+pub fn load_image<P: AsRef<Path>>(path: P) -> io::Result<Sysroot> {
+    let path = path.as_ref();
+    let store = if path.is_dir() { Store::Dir(path.to_path_buf()) } else { Store::open_tar(path)? };
+
+    let (format, config, layers) = if store.contains("manifest.json") {
+        let manifests: Vec<DockerSaveManifest> = store.read_json("manifest.json")?;
+        if manifests.len() > 1 {
+            return Err(invalid(format!("{} holds {} images; save a single image", path.display(), manifests.len())));
+        }
+        let manifest = manifests
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid("manifest.json lists no images".to_string()))?;
+        check_member(&manifest.config)?;
+        for layer in &manifest.layers {
+            check_member(layer)?;
+        }
+        ("docker save archive", Some(manifest.config), manifest.layers)
+    } else if store.contains("index.json") {
+        let (config, layers) = oci_layers(&store)?;
+        let format = if matches!(store, Store::Dir(_)) { "OCI image layout" } else { "OCI archive" };
+        (format, config, layers)
+    } else {
+        return Err(invalid(format!(
+            "{} is neither an OCI image layout nor a docker save archive (no index.json or manifest.json)",
+            path.display()
+        )));
+    };
+
+    let mut root = MemoryFs::default();
+    for layer in &layers {
+        apply_layer(&mut root, open_layer(&store, layer)?).map_err(|e| invalid(format!("layer {}: {}", layer, e)))?;
+    }
+
+    let config_user = config
+        .and_then(|config| store.read_json::<ImageConfig>(&config).ok())
+        .and_then(|config| config.config)
+        .map(|config| config.user)
+        .filter(|user| !user.is_empty());
+
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string());
+    Ok(Sysroot {
+        label: format!("image {}", file_name),
+        summary: format!(
+            "{} {}, {} layers flattened into {} paths",
+            format,
+            path.display(),
+            layers.len(),
+            root.len()
+        ),
+        config_user,
+        replay: None,
+        backing: Backing::Memory(root),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Member<'a> {
+        File(&'a str, &'a str),
+        Dir(&'a str),
+        HardLink(&'a str, &'a str),
+    }
+
+    fn layer(members: &[Member]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for member in members {
+            let mut header = tar::Header::new_gnu();
+            match *member {
+                Member::File(path, content) => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_mode(0o644);
+                    header.set_size(content.len() as u64);
+                    builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+                }
+                Member::Dir(path) => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_mode(0o755);
+                    header.set_size(0);
+                    builder.append_data(&mut header, path, io::empty()).unwrap();
+                }
+                Member::HardLink(path, target) => {
+                    header.set_entry_type(tar::EntryType::Link);
+                    header.set_size(0);
+                    builder.append_link(&mut header, path, target).unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn apply(root: &mut MemoryFs, members: &[Member]) {
+        apply_layer(root, layer(members).as_slice()).unwrap();
+    }
+
+    fn content(root: &MemoryFs, path: &str) -> String {
+        String::from_utf8(root.read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn whiteout_hides_a_lower_file() {
+        let mut root = MemoryFs::default();
+        apply(&mut root, &[Member::File("etc/hosts", "lower"), Member::File("etc/passwd", "root")]);
+        apply(&mut root, &[Member::File("etc/.wh.hosts", "")]);
+
+        assert!(root.entry("etc/hosts").is_none());
+        assert!(root.entry("etc/.wh.hosts").is_none());
+        assert_eq!(content(&root, "etc/passwd"), "root");
+    }
+
+    #[test]
+    fn opaque_whiteout_keeps_only_the_layers_own_files() {
+        let mut root = MemoryFs::default();
+        apply(&mut root, &[Member::File("etc/hosts", "lower"), Member::File("etc/group", "lower")]);
+        apply(&mut root, &[Member::File("etc/hosts", "upper"), Member::File("etc/.wh..wh..opq", "")]);
+
+        assert!(root.entry("etc").is_some());
+        assert!(root.entry("etc/group").is_none());
+        assert_eq!(content(&root, "etc/hosts"), "upper");
+    }
+
+    #[test]
+    fn hard_link_copies_a_lower_layer_file() {
+        let mut root = MemoryFs::default();
+        apply(&mut root, &[Member::File("etc/nsswitch.conf", "hosts: files")]);
+        apply(&mut root, &[Member::HardLink("usr/share/nsswitch.conf", "etc/nsswitch.conf")]);
+
+        assert_eq!(content(&root, "usr/share/nsswitch.conf"), "hosts: files");
+    }
+
+    #[test]
+    fn file_replaces_a_directory() {
+        let mut root = MemoryFs::default();
+        apply(&mut root, &[Member::Dir("etc/hosts"), Member::File("etc/hosts/extra", "x")]);
+        apply(&mut root, &[Member::File("etc/hosts", "127.0.0.1 localhost")]);
+
+        assert!(root.entry("etc/hosts/extra").is_none());
+        assert_eq!(content(&root, "etc/hosts"), "127.0.0.1 localhost");
+    }
+
+    fn descriptor(digest: &str, os: &str, architecture: &str) -> Descriptor {
+        Descriptor {
+            digest: digest.to_string(),
+            platform: Some(Platform {
+                architecture: architecture.to_string(),
+                os: os.to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn select_manifest_skips_attestations() {
+        let manifests = [
+            descriptor("sha256:aa", "unknown", "unknown"),
+            descriptor("sha256:bb", "linux", go_arch()),
+        ];
+        assert_eq!(select_manifest(&manifests).unwrap().digest, "sha256:bb");
+
+        // No match for this machine: fall back to any real platform
+        let manifests = [
+            descriptor("sha256:aa", "unknown", "unknown"),
+            descriptor("sha256:cc", "linux", "s390x-not-this-machine"),
+        ];
+        assert_eq!(select_manifest(&manifests).unwrap().digest, "sha256:cc");
+
+        assert!(select_manifest(&[descriptor("sha256:aa", "unknown", "unknown")]).is_none());
+    }
+
+    #[test]
+    fn digests_and_members_stay_inside_the_image() {
+        assert_eq!(blob_path("sha256:0a1B").unwrap(), "blobs/sha256/0a1B");
+        for digest in ["sha256:../../etc/passwd", "../x:00", "sha256:", "sha256", ":00", "sha256:00/01"] {
+            assert!(blob_path(digest).is_err(), "{}", digest);
+        }
+
+        assert!(check_member("abc/layer.tar").is_ok());
+        assert!(check_member("../layer.tar").is_err());
+        assert!(check_member("abc/../../layer.tar").is_err());
+        assert!(check_member("/etc/shadow").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use super::FileMeta;
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    /// `data` is `None` for files too large to keep in memory; their
+    /// metadata is still available.
+    File { data: Option<Vec<u8>>, size: u64 },
+    Dir,
+    Symlink(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub node: Node,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Entry {
+    pub fn dir() -> Self {
+        Entry {
+            node: Node::Dir,
+            mode: 0o755,
+            uid: 0,
+            gid: 0,
+        }
+    }
+}
+
+/// A read-only file tree held in memory, keyed by normalized path
+/// without the leading slash ("" is the root directory).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    entries: BTreeMap<String, Entry>,
+}
+
+pub fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("/{} does not exist", path))
+}
+
+impl MemoryFs {
+    pub fn insert(&mut self, path: &str, entry: Entry) {
+        let path = normalize(path);
+        let mut dir = parent(&path).to_string();
+        while !dir.is_empty() && !self.entries.contains_key(&dir) {
+            self.entries.insert(dir.clone(), Entry::dir());
+            dir = parent(&dir).to_string();
+        }
+        // Replacing a directory with a non-directory drops its old contents
+        if !matches!(entry.node, Node::Dir) {
+            self.remove_tree(&path);
+        }
+        self.entries.insert(path, entry);
+    }
+
+    pub fn remove_tree(&mut self, path: &str) {
+        let path = normalize(path);
+        let prefix = format!("{}/", path);
+        self.entries.retain(|key, _| key != &path && !key.starts_with(&prefix));
+    }
+
+    /// Hides everything below `path` while keeping the directory itself.
+    pub fn clear_dir(&mut self, path: &str) {
+        let path = normalize(path);
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        self.entries.retain(|key, _| key == &path || !key.starts_with(&prefix) || key.is_empty());
+    }
+
+    pub fn entry(&self, path: &str) -> Option<&Entry> {
+        self.entries.get(&normalize(path))
+    }
+
+    fn resolve(&self, path: &str) -> io::Result<String> {
+        super::resolve_within(path, |current| match self.entries.get(current) {
+            Some(Entry { node: Node::Symlink(target), .. }) => Some(target.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let resolved = self.resolve(path)?;
+        match self.entries.get(&resolved).map(|entry| &entry.node) {
+            Some(Node::File { data: Some(data), .. }) => Ok(data.clone()),
+            Some(Node::File { data: None, size }) => Err(io::Error::other(format!(
+                "/{} is {} bytes, too large to load from an image",
+                resolved, size
+            ))),
+            Some(_) => Err(io::Error::other(format!("/{} is not a regular file", resolved))),
+            None => Err(not_found(&resolved)),
+        }
+    }
+
+    pub fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let resolved = self.resolve(path)?;
+        match self.entries.get(&resolved) {
+            Some(Entry { node: Node::Dir, .. }) => {}
+            Some(_) => return Err(io::Error::other(format!("/{} is not a directory", resolved))),
+            None if resolved.is_empty() => {}
+            None => return Err(not_found(&resolved)),
+        }
+        let prefix = if resolved.is_empty() { String::new() } else { format!("{}/", resolved) };
+        Ok(self
+            .entries
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter_map(|(key, _)| {
+                let name = &key[prefix.len()..];
+                (!name.is_empty() && !name.contains('/')).then(|| name.to_string())
+            })
+            .collect())
+    }
+
+    pub fn metadata(&self, path: &str) -> io::Result<FileMeta> {
+        let resolved = self.resolve(path)?;
+        let entry = self.entries.get(&resolved).ok_or_else(|| not_found(&resolved))?;
+        Ok(FileMeta {
+            mode: entry.mode,
+            uid: entry.uid,
+            gid: entry.gid,
+            is_dir: matches!(entry.node, Node::Dir),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,143 @@
+pub mod image;
+pub mod memory;
+pub mod snapshot;
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub struct FileMeta {
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub is_dir: bool,
+}
+
+impl From<fs::Metadata> for FileMeta {
+    fn from(meta: fs::Metadata) -> Self {
+        FileMeta {
+            mode: meta.mode() & 0o7777,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            is_dir: meta.is_dir(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backing {
+    /// A directory on this machine used as `/`: a mounted disk image, a
+    /// chroot or a fixture tree.
+    Dir(PathBuf),
+    Memory(memory::MemoryFs),
+}
+
+/// A filesystem tree resolution reads from instead of the running system,
+/// such as a `--root` directory or the flattened layers of a container image.
+#[derive(Debug, Clone)]
+pub struct Sysroot {
+    backing: Backing,
+    /// Short name for source labels, e.g. "image web.tar".
+    pub label: String,
+    /// How the tree was assembled, for explanations.
+    pub summary: String,
+    /// The `User` the image config starts processes as, if any.
+    pub config_user: Option<String>,
+    /// Recorded live answers when the tree comes from a snapshot bundle.
+    pub replay: Option<snapshot::Replay>,
+}
+
+/// Follows symlinks in every component of `path` the way a chroot would:
+/// absolute targets and `..` never leave the root. `read_link` returns the
+/// target when the (normalized, root-relative) path is a symlink.
+fn resolve_within(path: &str, read_link: impl Fn(&str) -> Option<String>) -> io::Result<String> {
+    let mut pending: Vec<String> = memory::normalize(path).split('/').filter(|p| !p.is_empty()).map(String::from).rev().collect();
+    let mut resolved: Vec<String> = Vec::new();
+    let mut depth = 0;
+
+    while let Some(part) = pending.pop() {
+        if part == ".." {
+            resolved.pop();
+            continue;
+        }
+        resolved.push(part);
+        let current = resolved.join("/");
+        if let Some(target) = read_link(&current) {
+            depth += 1;
+            if depth > MAX_SYMLINK_DEPTH {
+                return Err(io::Error::other(format!("Too many levels of symbolic links at /{}", current)));
+            }
+            resolved.pop();
+            if target.starts_with('/') {
+                resolved.clear();
+            }
+            pending.extend(target.split('/').filter(|p| !p.is_empty() && *p != ".").map(String::from).rev());
+        }
+    }
+
+    Ok(resolved.join("/"))
+}
+
+impl Sysroot {
+    pub fn dir<P: AsRef<Path>>(path: P) -> io::Result<Sysroot> {
+        let path = path.as_ref();
+        if !fs::metadata(path)?.is_dir() {
+            return Err(io::Error::other(format!("{} is not a directory", path.display())));
+        }
+        Ok(Sysroot {
+            backing: Backing::Dir(path.to_path_buf()),
+            label: format!("root {}", path.display()),
+            summary: format!("directory {} is used as /", path.display()),
+            config_user: None,
+            replay: None,
+        })
+    }
+
+    /// Where `path` inside the root lives on this machine.
+    fn host_path(root: &Path, path: &str) -> io::Result<PathBuf> {
+        let resolved = resolve_within(path, |current| {
+            let host = root.join(current);
+            let is_link = fs::symlink_metadata(&host).is_ok_and(|m| m.file_type().is_symlink());
+            is_link.then(|| fs::read_link(&host).ok()).flatten().map(|t| t.to_string_lossy().to_string())
+        })?;
+        Ok(root.join(resolved))
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        match &self.backing {
+            Backing::Dir(root) => fs::read(Self::host_path(root, path)?),
+            Backing::Memory(tree) => tree.read(path),
+        }
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Names of the entries in a directory, sorted.
+    pub fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        match &self.backing {
+            Backing::Dir(root) => {
+                let mut names: Vec<String> = fs::read_dir(Self::host_path(root, path)?)?
+                    .flatten()
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect();
+                names.sort();
+                Ok(names)
+            }
+            Backing::Memory(tree) => tree.read_dir(path),
+        }
+    }
+
+    pub fn metadata(&self, path: &str) -> io::Result<FileMeta> {
+        match &self.backing {
+            Backing::Dir(root) => Ok(fs::metadata(Self::host_path(root, path)?)?.into()),
+            Backing::Memory(tree) => tree.metadata(path),
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::memory::{Entry, MemoryFs, Node};
+use super::{Backing, Sysroot};
+use crate::context::ResolveContext;
+use crate::dns;
+use crate::explain::decision_tree::DecisionStep;
+use crate::nss;
+use crate::proc;
+use crate::render::output::Database;
+use crate::resolver::{self, Resolver};
+
+pub const FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "snapshot.json";
+const ROOT_DIR: &str = "root";
+
+/// The [`RecordedAnswer::source`] of user namespace translations.
+pub const USERNS_SOURCE: &str = "userns";
+
+/// Files resolution reads, captured as the target process sees them.
+const CAPTURED_FILES: [&str; 11] = [
+    "/etc/nsswitch.conf",
+    "/etc/hosts",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/shells",
+    "/etc/login.defs",
+    "/etc/resolv.conf",
+    "/etc/mdns.allow",
+    "/etc/hostname",
+    "/run/systemd/resolve/resolv.conf",
+    proc::kubernetes::SERVICE_ACCOUNT_NAMESPACE,
+];
+
+/// Files whose second field may hold a password hash.
+const REDACTED_FILES: [&str; 2] = ["/etc/passwd", "/etc/group"];
+
+/// The steps a live source produced for one lookup, replayed in place of
+/// querying the source again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedAnswer {
+    pub database: String,
+    pub source: String,
+    pub name: String,
+    pub steps: Vec<DecisionStep>,
+    /// For the `userns` translation of a user or group lookup, the id the
+    /// answer has on the host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedFile {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+    /// Password fields were replaced with `x` before hashing.
+    #[serde(default)]
+    pub redacted: bool,
+    /// Permission bits and owner as the target process saw them, so the
+    /// audit's permission checks replay like they ran live.
+    #[serde(default = "default_mode")]
+    pub mode: u32,
+    #[serde(default)]
+    pub uid: u32,
+    #[serde(default)]
+    pub gid: u32,
+}
+
+fn default_mode() -> u32 {
+    0o644
+}
+
+/// `snapshot.json`, stored next to the captured files in the bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Seconds since the epoch.
+    pub created: u64,
+    pub hostname: Option<String>,
+    pub pid: Option<u32>,
+    pub namespaces: BTreeMap<String, String>,
+    pub files: Vec<CapturedFile>,
+    /// systemd-resolved per-link servers and domains at capture time.
+    pub resolved: Vec<String>,
+    pub resolved_error: Option<String>,
+    /// Context steps (container, netns, pod) of the captured process.
+    pub context: Vec<DecisionStep>,
+    pub answers: Vec<RecordedAnswer>,
+}
+
+/// What a snapshot-backed [`Sysroot`] replays besides its files.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub manifest: Manifest,
+}
+
+impl Replay {
+    pub fn answer(&self, database: &str, source: &str, name: &str) -> Option<&RecordedAnswer> {
+        self.manifest
+            .answers
+            .iter()
+            .find(|answer| answer.database == database && answer.source == source && answer.name == name)
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Replaces the password field of passwd/group lines with `x`.
+fn redact_passwords(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<&str> = line.split(':').collect();
+            if fields.len() > 2 && !line.trim_start().starts_with('#') {
+                fields[1] = "x";
+            }
+            fields.join(":") + "\n"
+        })
+        .collect()
+}
+
+/// Reads every file resolution may consult through `ctx`, so the capture
+/// follows `--pid`, `--netns` and `--root` like a lookup would.
+fn capture_files(ctx: &ResolveContext) -> Vec<(CapturedFile, Vec<u8>)> {
+    let mut paths: Vec<String> = CAPTURED_FILES.iter().map(|p| p.to_string()).collect();
+    for name in ctx.read_dir(nss::libvirt::DNSMASQ_DIR).unwrap_or_default() {
+        paths.push(format!("{}/{}", nss::libvirt::DNSMASQ_DIR, name));
+    }
+
+    let mut captured = Vec::new();
+    for path in paths {
+        let Ok(content) = ctx.read_to_string(&path) else {
+            continue;
+        };
+        let redacted = REDACTED_FILES.contains(&path.as_str());
+        let content = if redacted { redact_passwords(&content) } else { content }.into_bytes();
+        let meta = ctx.metadata(&path).ok();
+        captured.push((
+            CapturedFile {
+                sha256: sha256_hex(&content),
+                size: content.len() as u64,
+                path,
+                redacted,
+                mode: meta.as_ref().map_or_else(default_mode, |m| m.mode),
+                uid: meta.as_ref().map_or(0, |m| m.uid),
+                gid: meta.as_ref().map_or(0, |m| m.gid),
+            },
+            content,
+        ));
+    }
+    captured
+}
+
+/// Captures everything a bundle holds: the files, the live answers for
+/// `names` in each database, systemd-resolved's per-link state, the hostname
+/// and the namespace ids of the target.
+pub async fn capture(
+    resolver: &Resolver,
+    ctx: &ResolveContext,
+    names: &[(Database, &[String])],
+) -> (Manifest, Vec<(CapturedFile, Vec<u8>)>) {
+    let mut answers = Vec::new();
+    for (database, names) in names {
+        for name in names.iter() {
+            answers.extend(resolver.resolve(*database, name, ctx, &Default::default()).await.answers);
+        }
+    }
+
+    let (resolved, resolved_error) = if ctx.is_live() {
+        let state = match resolver.bus().connection().await {
+            Ok(connection) => dns::resolved::resolved_link_state(connection).await,
+            Err(e) => Err(e.into()),
+        };
+        match state {
+            Ok(lines) => (lines, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        }
+    } else {
+        (Vec::new(), Some(format!("not captured from {}", ctx.sysroot.as_ref().map(|s| s.label.as_str()).unwrap_or("a file tree"))))
+    };
+    // The kernel's hostname is the host's own unless a process was targeted
+    let hostname = match ctx.pid {
+        Some(_) => ctx.read_to_string("/etc/hostname").ok(),
+        None if ctx.is_live() => fs::read_to_string("/proc/sys/kernel/hostname").ok(),
+        None => ctx.read_to_string("/etc/hostname").ok(),
+    }
+    .map(|h| h.trim().to_string())
+    .filter(|h| !h.is_empty());
+
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        created: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        hostname,
+        pid: ctx.pid,
+        namespaces: if ctx.is_live() {
+            proc::namespaces::namespace_ids(ctx.pid.unwrap_or_else(std::process::id))
+        } else {
+            Default::default()
+        },
+        files: Vec::new(),
+        resolved,
+        resolved_error,
+        context: resolver::context_steps(ctx),
+        answers,
+    };
+    (manifest, capture_files(ctx))
+}
+
+fn append(builder: &mut tar::Builder<fs::File>, name: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+/// Writes the bundle: `snapshot.json` followed by the files under `root/`.
+/// Headers carry no timestamps or owners, so equal captures give equal bundles.
+pub fn write_bundle<P: AsRef<Path>>(path: P, mut manifest: Manifest, files: Vec<(CapturedFile, Vec<u8>)>) -> io::Result<()> {
+    manifest.files = files.iter().map(|(file, _)| file.clone()).collect();
+    let json = serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?;
+
+    let mut builder = tar::Builder::new(fs::File::create(path)?);
+    append(&mut builder, MANIFEST_NAME, &json)?;
+    for (file, content) in &files {
+        append(&mut builder, &format!("{}{}", ROOT_DIR, file.path), content)?;
+    }
+    builder.into_inner()?.sync_all()
+}
+
+/// Loads a bundle written by `resolve snapshot`, checking every file against
+/// the hash recorded at capture time.
+pub fn load_snapshot<P: AsRef<Path>>(path: P) -> io::Result<Sysroot> {
+    let path = path.as_ref();
+    let mut archive = tar::Archive::new(fs::File::open(path)?);
+    let mut manifest: Option<Manifest> = None;
+    let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if name == MANIFEST_NAME {
+            manifest = Some(serde_json::from_slice(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+        } else if let Some(file) = name.strip_prefix(ROOT_DIR).filter(|f| f.starts_with('/')) {
+            contents.insert(file.to_string(), data);
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{} has no {}", path.display(), MANIFEST_NAME))
+    })?;
+    if manifest.version != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("snapshot format version {} is not supported (expected {})", manifest.version, FORMAT_VERSION),
+        ));
+    }
+
+    let mut tree = MemoryFs::default();
+    for file in &manifest.files {
+        let data = contents.remove(&file.path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{} is listed but missing from the bundle", file.path))
+        })?;
+        if sha256_hex(&data) != file.sha256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} does not match its recorded sha256 {}", file.path, file.sha256),
+            ));
+        }
+        tree.insert(
+            &file.path,
+            Entry {
+                node: Node::File {
+                    size: data.len() as u64,
+                    data: Some(data),
+                },
+                mode: file.mode,
+                uid: file.uid,
+                gid: file.gid,
+            },
+        );
+    }
+
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string());
+    let mut summary = format!(
+        "snapshot of {} taken at unix time {}",
+        manifest.hostname.as_deref().unwrap_or("(unknown host)"),
+        manifest.created
+    );
+    if let Some(pid) = manifest.pid {
+        summary.push_str(&format!(" for pid {}", pid));
+    }
+    if let Some(net) = manifest.namespaces.get("net") {
+        summary.push_str(&format!(" in {}", net));
+    }
+    summary.push_str(&format!(
+        "; {} files verified against their sha256, {} recorded answers",
+        manifest.files.len(),
+        manifest.answers.len()
+    ));
+
+    Ok(Sysroot {
+        backing: Backing::Memory(tree),
+        label: format!("snapshot {}", file_name),
+        summary,
+        config_user: None,
+        replay: Some(Replay { manifest }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("resolve-snapshot-test-{}-{}.tar", name, std::process::id()))
+    }
+
+    fn captured(path: &str, content: &str, mode: u32, uid: u32, gid: u32) -> (CapturedFile, Vec<u8>) {
+        (
+            CapturedFile {
+                path: path.to_string(),
+                sha256: sha256_hex(content.as_bytes()),
+                size: content.len() as u64,
+                redacted: false,
+                mode,
+                uid,
+                gid,
+            },
+            content.as_bytes().to_vec(),
+        )
+    }
+
+    fn manifest() -> Manifest {
+        Manifest {
+            version: FORMAT_VERSION,
+            created: 1_700_000_000,
+            hostname: Some("web-1".to_string()),
+            pid: Some(42),
+            namespaces: BTreeMap::new(),
+            files: Vec::new(),
+            resolved: Vec::new(),
+            resolved_error: None,
+            context: Vec::new(),
+            answers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bundle_round_trips_contents_and_ownership() {
+        let path = bundle_path("round-trip");
+        let files = vec![
+            captured("/etc/hosts", "10.0.0.5 db.internal db\n", 0o644, 0, 0),
+            captured("/etc/nsswitch.conf", "hosts: files dns\n", 0o600, 1000, 1000),
+        ];
+        write_bundle(&path, manifest(), files).unwrap();
+        let sysroot = load_snapshot(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(sysroot.read_to_string("/etc/hosts").unwrap(), "10.0.0.5 db.internal db\n");
+        let meta = sysroot.metadata("/etc/nsswitch.conf").unwrap();
+        assert_eq!((meta.mode, meta.uid, meta.gid), (0o600, 1000, 1000));
+        assert_eq!(sysroot.replay.unwrap().manifest.pid, Some(42));
+    }
+
+    #[test]
+    fn tampered_file_fails_the_hash_check() {
+        let path = bundle_path("tampered");
+        let (mut file, _) = captured("/etc/hosts", "10.0.0.5 db\n", 0o644, 0, 0);
+        file.size = 12;
+        write_bundle(&path, manifest(), vec![(file, b"10.6.6.6 db\n".to_vec())]).unwrap();
+        let error = load_snapshot(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("/etc/hosts does not match its recorded sha256"));
+    }
+
+    #[test]
+    fn redact_passwords_keeps_comments_and_short_lines() {
+        let content = "root:$6$salt$hash:0:0:root:/root:/bin/bash\n# admin:secret:1:1\nwheel:!:10:root\nbroken\n";
+        assert_eq!(
+            redact_passwords(content),
+            "root:x:0:0:root:/root:/bin/bash\n# admin:secret:1:1\nwheel:x:10:root\nbroken\n"
+        );
+    }
+}
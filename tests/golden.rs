@@ -0,0 +1,120 @@
+//! Golden tests: `resolve --root` against the fixture tree in
+//! tests/fixtures/root, compared with the expected output in tests/golden.
+//! Run with `UPDATE_GOLDEN=1` to rewrite the expected files.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const ROOT: &str = "tests/fixtures/root";
+
+fn golden(name: &str, args: &[&str], status: i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_resolve"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--root", ROOT])
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env_remove("RESOLVE_PLUGIN_PATH")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(status), "{}: {}", name, String::from_utf8_lossy(&output.stderr));
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &stdout).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    assert_eq!(stdout, expected, "output of {:?} differs from {}", args, path.display());
+}
+
+#[test]
+fn host_from_files() {
+    golden("host-files", &["host", "web", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn host_shadowed_by_all_sources() {
+    golden("host-all-sources", &["host", "db", "--all-sources", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn host_numeric() {
+    golden("host-numeric", &["host", "0x7f.1", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn host_offline_sources() {
+    golden("host-offline", &["host", "printer.local", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn host_expect_why_not() {
+    golden("host-why-not", &["host", "db.internal", "--expect", "10.0.0.9", "--why-not", "--format", "plain"], 1);
+}
+
+#[test]
+fn host_getent() {
+    golden("host-getent", &["host", "localhost", "--format", "getent"], 0);
+}
+
+#[test]
+fn host_getent_aliases() {
+    golden("host-getent-aliases", &["host", "db", "--format", "getent"], 0);
+}
+
+#[test]
+fn host_getent_address() {
+    golden("host-getent-address", &["host", "10.0.0.5", "--format", "getent"], 0);
+}
+
+#[test]
+fn user_from_files() {
+    golden("user-files", &["user", "deploy", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn user_getent() {
+    golden("user-getent", &["user", "deploy", "--format", "getent"], 0);
+}
+
+#[test]
+fn user_mymachines_offline() {
+    golden("user-mymachines-offline", &["user", "vu-web-1000", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn group_from_files() {
+    golden("group-files", &["group", "docker", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn group_notfound_return() {
+    golden("group-notfound-return", &["group", "ghosts", "--why", "--format", "plain"], 0);
+}
+
+#[test]
+fn group_mermaid() {
+    golden("group-mermaid", &["group", "docker", "--format", "mermaid"], 0);
+}
+
+#[test]
+fn audit_identities() {
+    golden("audit-identities", &["audit", "identities", "--format", "plain"], 0);
+}
+
+#[test]
+fn audit_dns() {
+    golden("audit-dns", &["audit", "dns", "--format", "plain"], 0);
+}
+
+#[test]
+fn audit_dns_ndjson() {
+    golden("audit-dns-ndjson", &["audit", "dns", "--format", "ndjson"], 0);
+}
+
+#[test]
+fn check_json() {
+    golden("check-json", &["check", "tests/fixtures/expectations.yaml", "--format", "json"], 1);
+}